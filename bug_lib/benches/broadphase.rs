@@ -0,0 +1,97 @@
+//! Compares finding overlaps with the spatial hash against checking every pair of colliders.
+//!
+//! Run with `cargo bench -p bug_lib`.
+
+use bevy::prelude::*;
+use bug_lib::collision::{
+    prelude::{CollisionLayers, SpatialHash},
+    Collider,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const ENEMIES: usize = 10_000;
+const ARENA: f32 = 2000.;
+
+fn random_colliders(
+    count: usize,
+    radius: f32,
+    rng: &mut StdRng,
+) -> Vec<(Entity, Collider, Transform)> {
+    (0..count)
+        .map(|i| {
+            (
+                Entity::from_raw(i as u32),
+                Collider::circle(radius),
+                Transform::from_xyz(
+                    rng.gen_range(-ARENA..ARENA),
+                    rng.gen_range(-ARENA..ARENA),
+                    0.,
+                ),
+            )
+        })
+        .collect()
+}
+
+fn naive_overlaps(
+    a: &[(Entity, Collider, Transform)],
+    b: &[(Entity, Collider, Transform)],
+) -> usize {
+    let mut count = 0;
+    for (_, ac, at) in a {
+        for (_, bc, bt) in b {
+            if ac.collides_with(at, bc, bt) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn hashed_overlaps(
+    a: &[(Entity, Collider, Transform)],
+    b: &[(Entity, Collider, Transform)],
+) -> usize {
+    let mut hash = SpatialHash::default();
+    for (e, c, t) in b {
+        hash.insert(
+            *e,
+            t.translation.xy(),
+            c.bounding_radius(),
+            CollisionLayers::default(),
+        );
+    }
+
+    a.iter()
+        .map(|(_, c, t)| {
+            hash.query_circle(t.translation.xy(), c.bounding_radius())
+                .count()
+        })
+        .sum()
+}
+
+fn broadphase(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let enemies = random_colliders(ENEMIES, 32., &mut rng);
+
+    let mut group = c.benchmark_group("enemies_vs_colliders");
+    for others in [1, 100, 1000] {
+        // Swatters, projectiles, pickups and the like
+        let colliders = random_colliders(others, 16., &mut rng);
+
+        group.bench_with_input(
+            BenchmarkId::new("naive", others),
+            &colliders,
+            |b, colliders| b.iter(|| naive_overlaps(black_box(colliders), black_box(&enemies))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("spatial_hash", others),
+            &colliders,
+            |b, colliders| b.iter(|| hashed_overlaps(black_box(colliders), black_box(&enemies))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, broadphase);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-use crate::state::AppState;
+use crate::{enemy::prelude::EnemyDefinition, state::AppState, wave::prelude::WaveScript};
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
@@ -22,12 +22,11 @@ pub struct AppAssets {
     pub font: Handle<Font>,
     #[asset(path = "embedded://sprites/robot1.png")]
     pub robot1_sprite: Handle<Image>,
-    #[asset(path = "embedded://sprites/enemy1.png")]
-    pub enemy1_sprite: Handle<Image>,
-    #[asset(path = "embedded://sprites/enemy2.png")]
-    pub enemy2_sprite: Handle<Image>,
-    #[asset(path = "embedded://sprites/enemy3.png")]
-    pub enemy3_sprite: Handle<Image>,
+    /// The built in enemy types, which bring their own sprites along
+    #[asset(path = "embedded://enemies", collection(typed))]
+    pub enemies: Vec<Handle<EnemyDefinition>>,
+    #[asset(path = "embedded://waves/default.waves.ron")]
+    pub waves: Handle<WaveScript>,
     #[asset(path = "embedded://sprites/background.png")]
     pub background_image: Handle<Image>,
     #[asset(path = "embedded://audio/DebuggerBODY.mp3")]
@@ -0,0 +1,126 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::prelude::{ColliderQuery, SpatialHash};
+
+/// Where and how deeply two colliders overlap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub point: Vec2,
+    /// Points from the first entity towards the second
+    pub normal: Vec2,
+    pub penetration: f32,
+}
+
+/// Sent on the first fixed tick two colliders overlap
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CollisionStarted {
+    pub a: Entity,
+    pub b: Entity,
+    pub contact: Contact,
+}
+
+/// Sent every fixed tick two colliders overlap, including the first
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Colliding {
+    pub a: Entity,
+    pub b: Entity,
+    pub contact: Contact,
+}
+
+/// Sent on the first fixed tick two colliders stop overlapping, or one of them goes away
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CollisionEnded {
+    pub a: Entity,
+    pub b: Entity,
+}
+
+/// Orders a pair of entities so the one matching `first` comes first, if either of them does
+fn order_pair(a: Entity, b: Entity, first: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+    if first(a) {
+        Some((a, b))
+    } else if first(b) {
+        Some((b, a))
+    } else {
+        None
+    }
+}
+
+impl CollisionStarted {
+    pub fn ordered(&self, first: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+        order_pair(self.a, self.b, first)
+    }
+}
+
+impl Colliding {
+    pub fn ordered(&self, first: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+        order_pair(self.a, self.b, first)
+    }
+}
+
+impl CollisionEnded {
+    pub fn ordered(&self, first: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+        order_pair(self.a, self.b, first)
+    }
+}
+
+/// Every pair of colliders that overlapped on the last fixed tick
+#[derive(Resource, Default)]
+pub struct Collisions(HashMap<(Entity, Entity), Contact>);
+
+impl Collisions {
+    pub fn contains(&self, a: Entity, b: Entity) -> bool {
+        self.0.contains_key(&(a.min(b), a.max(b)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity, &Contact)> {
+        self.0.iter().map(|((a, b), c)| (*a, *b, c))
+    }
+}
+
+/// Finds every overlapping pair of colliders and reports what changed since the last tick
+pub fn detect_collisions(
+    q: ColliderQuery,
+    hash: Res<SpatialHash>,
+    mut collisions: ResMut<Collisions>,
+    mut started: EventWriter<CollisionStarted>,
+    mut colliding: EventWriter<Colliding>,
+    mut ended: EventWriter<CollisionEnded>,
+) {
+    let mut current = HashMap::default();
+
+    for (a, ac, at, al) in q.iter() {
+        let layers = al.copied().unwrap_or_default();
+        for entry in
+            hash.query_circle_with_layers(at.translation.xy(), ac.bounding_radius(), layers)
+        {
+            // Each pair only needs checking once
+            let b = entry.entity;
+            if b <= a {
+                continue;
+            }
+
+            let Ok((_, bc, bt, _)) = q.get(b) else {
+                continue;
+            };
+
+            if let Some(contact) = ac.contact(at, bc, bt) {
+                current.insert((a, b), contact);
+            }
+        }
+    }
+
+    for (&(a, b), &contact) in current.iter() {
+        if !collisions.0.contains_key(&(a, b)) {
+            started.send(CollisionStarted { a, b, contact });
+        }
+        colliding.send(Colliding { a, b, contact });
+    }
+
+    for &(a, b) in collisions.0.keys() {
+        if !current.contains_key(&(a, b)) {
+            ended.send(CollisionEnded { a, b });
+        }
+    }
+
+    collisions.0 = current;
+}
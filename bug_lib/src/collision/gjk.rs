@@ -0,0 +1,659 @@
+//! Contact generation between any two convex shapes, using GJK to find how far apart they are
+//! and EPA to find how deeply they overlap
+
+use bevy::prelude::*;
+
+use super::prelude::Contact;
+
+const MAX_ITERATIONS: usize = 32;
+const TOLERANCE: f32 = 1e-4;
+
+/// The solid part of a shape, in world space, before it's inflated by a margin
+#[derive(Debug, Clone, Copy)]
+pub enum Core {
+    Point(Vec2),
+    Segment(Vec2, Vec2),
+    Aabb {
+        center: Vec2,
+        half_extents: Vec2,
+    },
+    /// A slice of a circle no wider than a half circle, so it stays convex
+    Sector {
+        apex: Vec2,
+        radius: f32,
+        /// Unit vector down the middle of the slice
+        direction: Vec2,
+        half_angle: f32,
+    },
+}
+
+/// A convex shape described as a core grown outwards by `margin` in every direction
+#[derive(Debug, Clone, Copy)]
+pub struct Convex {
+    pub core: Core,
+    pub margin: f32,
+}
+
+impl Convex {
+    pub fn new(core: Core, margin: f32) -> Self {
+        Convex { core, margin }
+    }
+
+    fn translate(&mut self, offset: Vec2) {
+        match &mut self.core {
+            Core::Point(p) => *p += offset,
+            Core::Segment(a, b) => {
+                *a += offset;
+                *b += offset;
+            }
+            Core::Aabb { center, .. } => *center += offset,
+            Core::Sector { apex, .. } => *apex += offset,
+        }
+    }
+
+    fn center(&self) -> Vec2 {
+        match self.core {
+            Core::Point(p) => p,
+            Core::Segment(a, b) => (a + b) / 2.,
+            Core::Aabb { center, .. } => center,
+            Core::Sector {
+                apex,
+                radius,
+                direction,
+                ..
+            } => apex + direction * radius / 2.,
+        }
+    }
+
+    /// The point of the core furthest along a direction
+    fn core_support(&self, d: Vec2) -> Vec2 {
+        match self.core {
+            Core::Point(p) => p,
+            Core::Segment(a, b) => {
+                if a.dot(d) >= b.dot(d) {
+                    a
+                } else {
+                    b
+                }
+            }
+            Core::Aabb {
+                center,
+                half_extents,
+            } => center + half_extents * Vec2::select(d.cmpge(Vec2::ZERO), Vec2::ONE, -Vec2::ONE),
+            Core::Sector {
+                apex,
+                radius,
+                direction,
+                half_angle,
+            } => {
+                let Some(d) = d.try_normalize() else {
+                    return apex;
+                };
+
+                // Inside the slice the arc is always furthest out, otherwise it's one of the
+                // corners
+                if direction.angle_between(d).abs() <= half_angle {
+                    return apex + d * radius;
+                }
+
+                let left = apex + Vec2::from_angle(half_angle).rotate(direction) * radius;
+                let right = apex + Vec2::from_angle(-half_angle).rotate(direction) * radius;
+                [apex, left, right]
+                    .into_iter()
+                    .max_by(|a, b| a.dot(d).total_cmp(&b.dot(d)))
+                    .unwrap_or(apex)
+            }
+        }
+    }
+
+    fn support(&self, d: Vec2) -> Vec2 {
+        self.core_support(d) + d.normalize_or_zero() * self.margin
+    }
+}
+
+/// A point on the Minkowski difference of two shapes, and the points on each shape that made it
+#[derive(Debug, Clone, Copy)]
+struct SupportPoint {
+    a: Vec2,
+    b: Vec2,
+    p: Vec2,
+}
+
+fn support(a: &Convex, b: &Convex, d: Vec2, with_margin: bool) -> SupportPoint {
+    let (pa, pb) = if with_margin {
+        (a.support(d), b.support(-d))
+    } else {
+        (a.core_support(d), b.core_support(-d))
+    };
+
+    SupportPoint {
+        a: pa,
+        b: pb,
+        p: pa - pb,
+    }
+}
+
+/// The closest point to the origin on a segment, as a weight on its end
+fn segment_weight(a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared <= f32::EPSILON {
+        return 0.;
+    }
+
+    (-a.dot(ab) / length_squared).clamp(0., 1.)
+}
+
+/// Shrinks a simplex down to the part closest to the origin, returning the weight of each
+/// remaining point, or `None` if the origin is inside it
+fn reduce(simplex: &mut Vec<SupportPoint>) -> Option<Vec<f32>> {
+    match simplex.len() {
+        1 => Some(vec![1.]),
+        2 => {
+            let t = segment_weight(simplex[0].p, simplex[1].p);
+            if t <= 0. {
+                simplex.truncate(1);
+                Some(vec![1.])
+            } else if t >= 1. {
+                simplex.remove(0);
+                Some(vec![1.])
+            } else {
+                Some(vec![1. - t, t])
+            }
+        }
+        _ => {
+            let (a, b, c) = (simplex[0].p, simplex[1].p, simplex[2].p);
+            let ab = (b - a).perp_dot(-a);
+            let bc = (c - b).perp_dot(-b);
+            let ca = (a - c).perp_dot(-c);
+            let inside = (ab >= 0. && bc >= 0. && ca >= 0.) || (ab <= 0. && bc <= 0. && ca <= 0.);
+            if inside {
+                return None;
+            }
+
+            // Otherwise the closest point is on whichever edge is nearest
+            let (i, j, _) = [(0, 1), (1, 2), (2, 0)]
+                .into_iter()
+                .map(|(i, j)| (i, j, segment_weight(simplex[i].p, simplex[j].p)))
+                .min_by(|(i, j, t), (k, l, u)| {
+                    let d1 = simplex[*i].p.lerp(simplex[*j].p, *t).length_squared();
+                    let d2 = simplex[*k].p.lerp(simplex[*l].p, *u).length_squared();
+                    d1.total_cmp(&d2)
+                })?;
+
+            *simplex = vec![simplex[i], simplex[j]];
+            reduce(simplex)
+        }
+    }
+}
+
+/// Runs GJK, returning the simplex it ended on and the weights of its points, which are `None`
+/// if the shapes overlap
+fn gjk(a: &Convex, b: &Convex, with_margin: bool) -> (Vec<SupportPoint>, Option<Vec<f32>>) {
+    let initial = b.center() - a.center();
+    let initial = if initial.length_squared() > f32::EPSILON {
+        initial
+    } else {
+        Vec2::X
+    };
+
+    let mut simplex = vec![support(a, b, initial, with_margin)];
+    let mut weights = Some(vec![1.]);
+
+    for _ in 0..MAX_ITERATIONS {
+        weights = reduce(&mut simplex);
+        let Some(w) = &weights else {
+            break;
+        };
+
+        let closest = closest_point(&simplex, w);
+        if closest.length_squared() <= TOLERANCE * TOLERANCE {
+            weights = None;
+            break;
+        }
+
+        // Stop once the next support point can't get any closer to the origin
+        let d = -closest;
+        let next = support(a, b, d, with_margin);
+        if next.p.dot(d) - closest.dot(d) <= TOLERANCE * d.length() {
+            break;
+        }
+
+        simplex.push(next);
+    }
+
+    (simplex, weights)
+}
+
+fn closest_point(simplex: &[SupportPoint], weights: &[f32]) -> Vec2 {
+    simplex
+        .iter()
+        .zip(weights)
+        .fold(Vec2::ZERO, |acc, (s, w)| acc + s.p * *w)
+}
+
+/// The closest points on each shape's core, as long as the cores don't overlap
+fn closest_cores(a: &Convex, b: &Convex) -> Option<(Vec2, Vec2)> {
+    let (simplex, weights) = gjk(a, b, false);
+    let weights = weights?;
+
+    let on_a = simplex
+        .iter()
+        .zip(&weights)
+        .fold(Vec2::ZERO, |acc, (s, w)| acc + s.a * *w);
+    let on_b = simplex
+        .iter()
+        .zip(&weights)
+        .fold(Vec2::ZERO, |acc, (s, w)| acc + s.b * *w);
+    Some((on_a, on_b))
+}
+
+/// Where two convex shapes overlap, if they do. The normal points from `a` towards `b`.
+pub fn contact(a: &Convex, b: &Convex) -> Option<Contact> {
+    // When only the margins overlap the closest points on the cores give an exact answer,
+    // which covers the common cases of circles and capsules without needing EPA at all
+    if let Some((on_a, on_b)) = closest_cores(a, b) {
+        let distance = on_a.distance(on_b);
+        let margins = a.margin + b.margin;
+        if distance > margins {
+            return None;
+        }
+
+        // Cores that only just touch have no direction between them, so fall back on the centers
+        let normal = (on_b - on_a)
+            .try_normalize()
+            .unwrap_or_else(|| (b.center() - a.center()).try_normalize().unwrap_or(Vec2::X));
+        let surface_a = on_a + normal * a.margin;
+        let surface_b = on_b - normal * b.margin;
+
+        return Some(Contact {
+            point: (surface_a + surface_b) / 2.,
+            normal,
+            penetration: margins - distance,
+        });
+    }
+
+    epa(a, b)
+}
+
+/// How far apart two shapes are, with the closest point on `b` and the direction from `a`
+/// towards it, or `None` if they touch
+pub fn separation(a: &Convex, b: &Convex) -> Option<(f32, Vec2, Vec2)> {
+    let (on_a, on_b) = closest_cores(a, b)?;
+    let distance = on_a.distance(on_b) - a.margin - b.margin;
+    if distance <= 0. {
+        return None;
+    }
+
+    let normal = (on_b - on_a).normalize();
+    Some((distance, on_b - normal * b.margin, normal))
+}
+
+/// Slides `a` along a unit direction until it touches `b`, returning how far it went, where it
+/// touched and the surface normal of `b` there
+pub fn sweep(
+    a: &Convex,
+    direction: Vec2,
+    max_distance: f32,
+    b: &Convex,
+) -> Option<(f32, Vec2, Vec2)> {
+    let mut a = *a;
+    let mut travelled = 0.;
+
+    // Conservative advancement, moving as far as the gap allows each step can never overshoot
+    for _ in 0..MAX_ITERATIONS {
+        let Some((gap, point, normal)) = separation(&a, b) else {
+            // Only just touching after a step, or overlapping before even moving
+            return Some(match contact(&a, b) {
+                Some(c) => (travelled, c.point, -c.normal),
+                None => (travelled, a.support(direction), -direction),
+            });
+        };
+
+        // Moving away or sideways will never close the gap
+        let approach = normal.dot(direction);
+        if approach <= 0. {
+            return None;
+        }
+
+        if gap <= TOLERANCE {
+            return Some((travelled, point, -normal));
+        }
+
+        let step = gap / approach;
+        travelled += step;
+        if travelled > max_distance {
+            return None;
+        }
+        a.translate(direction * step);
+    }
+
+    None
+}
+
+/// Works out the shortest way to push two overlapping shapes apart
+fn epa(a: &Convex, b: &Convex) -> Option<Contact> {
+    let (mut polytope, weights) = gjk(a, b, true);
+    if weights.is_some() {
+        return None;
+    }
+
+    // GJK can finish early with the origin on an edge or a corner, so fill out a triangle
+    if polytope.len() == 1 {
+        let d = -polytope[0].p;
+        let d = if d.length_squared() > f32::EPSILON {
+            d
+        } else {
+            Vec2::X
+        };
+        polytope.push(support(a, b, d, true));
+    }
+    if polytope.len() == 2 {
+        let edge = polytope[1].p - polytope[0].p;
+        let next = support(a, b, edge.perp(), true);
+        let next = if (next.p - polytope[0].p).perp_dot(edge).abs() > TOLERANCE {
+            next
+        } else {
+            support(a, b, -edge.perp(), true)
+        };
+        polytope.push(next);
+    }
+
+    // Keep the winding counter-clockwise so edge normals point outwards
+    let area = (polytope[1].p - polytope[0].p).perp_dot(polytope[2].p - polytope[0].p);
+    if area.abs() <= f32::EPSILON {
+        let point = (a.center() + b.center()) / 2.;
+        return Some(Contact {
+            point,
+            normal: (b.center() - a.center()).try_normalize().unwrap_or(Vec2::X),
+            penetration: 0.,
+        });
+    }
+    if area < 0. {
+        polytope.swap(1, 2);
+    }
+
+    let mut best = (0, Vec2::X, 0.);
+    for _ in 0..MAX_ITERATIONS {
+        best = (0..polytope.len())
+            .map(|i| {
+                let j = (i + 1) % polytope.len();
+                let edge = polytope[j].p - polytope[i].p;
+                let normal = Vec2::new(edge.y, -edge.x).normalize_or_zero();
+                (i, normal, normal.dot(polytope[i].p))
+            })
+            .min_by(|x, y| x.2.total_cmp(&y.2))?;
+
+        let (i, normal, distance) = best;
+        let next = support(a, b, normal, true);
+        if next.p.dot(normal) - distance <= TOLERANCE {
+            break;
+        }
+
+        polytope.insert(i + 1, next);
+    }
+
+    let (i, normal, penetration) = best;
+    let j = (i + 1) % polytope.len();
+    let t = segment_weight(polytope[i].p, polytope[j].p);
+    let on_a = polytope[i].a.lerp(polytope[j].a, t);
+
+    Some(Contact {
+        point: on_a - normal * penetration / 2.,
+        normal,
+        penetration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_4, PI};
+
+    use crate::collision::Collider;
+
+    use super::*;
+
+    fn circle(center: Vec2, radius: f32) -> Convex {
+        Convex::new(Core::Point(center), radius)
+    }
+
+    fn aabb(center: Vec2, half_extents: Vec2) -> Convex {
+        Convex::new(
+            Core::Aabb {
+                center,
+                half_extents,
+            },
+            0.,
+        )
+    }
+
+    fn capsule(a: Vec2, b: Vec2, radius: f32) -> Convex {
+        Convex::new(Core::Segment(a, b), radius)
+    }
+
+    fn sector(apex: Vec2, radius: f32, direction: Vec2, half_angle: f32) -> Convex {
+        Convex::new(
+            Core::Sector {
+                apex,
+                radius,
+                direction,
+                half_angle,
+            },
+            0.,
+        )
+    }
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert_within(a, b, 1e-3);
+    }
+
+    /// GJK only ever closes in on the closest point of an arc, so those need some slack
+    fn assert_within(a: Vec2, b: Vec2, tolerance: f32) {
+        assert!(
+            a.distance(b) < tolerance,
+            "{a} is not within {tolerance} of {b}"
+        );
+    }
+
+    #[test]
+    fn overlapping_circles_touch() {
+        let c = contact(&circle(Vec2::ZERO, 10.), &circle(Vec2::new(15., 0.), 10.)).unwrap();
+        assert_close(c.normal, Vec2::X);
+        assert!((c.penetration - 5.).abs() < 1e-3);
+        assert_close(c.point, Vec2::new(7.5, 0.));
+    }
+
+    #[test]
+    fn distant_circles_dont_touch() {
+        assert!(contact(&circle(Vec2::ZERO, 10.), &circle(Vec2::new(25., 0.), 10.)).is_none());
+    }
+
+    #[test]
+    fn circle_touches_box() {
+        let c = contact(
+            &circle(Vec2::ZERO, 10.),
+            &aabb(Vec2::new(14., 0.), Vec2::splat(5.)),
+        )
+        .unwrap();
+        assert_close(c.normal, Vec2::X);
+        assert!((c.penetration - 1.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn circle_touches_capsule() {
+        let a = capsule(Vec2::new(-20., 0.), Vec2::new(20., 0.), 5.);
+
+        // Along the side
+        let c = contact(&a, &circle(Vec2::new(10., 12.), 10.)).unwrap();
+        assert_close(c.normal, Vec2::Y);
+        assert!((c.penetration - 3.).abs() < 1e-3);
+        assert_close(c.point, Vec2::new(10., 3.5));
+
+        // Off the rounded end
+        let c = contact(&a, &circle(Vec2::new(30., 0.), 10.)).unwrap();
+        assert_close(c.normal, Vec2::X);
+        assert!((c.penetration - 5.).abs() < 1e-3);
+
+        assert!(contact(&a, &circle(Vec2::new(0., 16.), 10.)).is_none());
+    }
+
+    #[test]
+    fn box_touches_sector() {
+        // The cores overlap, so this goes through EPA. The arc pokes furthest out along the
+        // middle of the slice, so that's the shallowest way out.
+        let c = contact(
+            &sector(Vec2::ZERO, 20., Vec2::X, FRAC_PI_4),
+            &aabb(Vec2::new(25., 0.), Vec2::splat(10.)),
+        )
+        .unwrap();
+        assert_close(c.normal, Vec2::X);
+        assert!((c.penetration - 5.).abs() < 1e-3);
+
+        // Beside the slice, where it would only touch if it were a full circle
+        assert!(contact(
+            &sector(Vec2::ZERO, 20., Vec2::X, FRAC_PI_4),
+            &aabb(Vec2::new(0., 15.), Vec2::splat(5.)),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn wide_sectors_are_split_in_two() {
+        // Three quarters of a circle, with the gap facing left
+        let wide = Collider::sector(20., 1.5 * PI);
+        let at = |x: f32, y: f32| Transform::from_xyz(x, y, 0.);
+
+        // Straight up is further round than either half would reach on its own
+        let c = wide
+            .contact(&at(0., 0.), &Collider::circle(10.), &at(0., 25.))
+            .unwrap();
+        assert_within(c.normal, Vec2::Y, 1e-2);
+        assert!((c.penetration - 5.).abs() < 1e-3);
+
+        // In the gap, which the convex hull of the whole sector would cover
+        assert!(wide
+            .contact(&at(0., 0.), &Collider::circle(3.), &at(-10., 0.))
+            .is_none());
+
+        // Just above the middle of the gap only reaches the upper edge
+        let c = wide
+            .contact(&at(0., 0.), &Collider::circle(8.), &at(-10., 2.))
+            .unwrap();
+        assert_close(c.normal, Vec2::new(-1., -1.).normalize());
+        assert!((c.penetration - (8. - 4. * 2_f32.sqrt())).abs() < 1e-3);
+    }
+
+    #[test]
+    fn deep_overlap_uses_the_shallowest_axis() {
+        // The cores overlap, so this goes through EPA
+        let c = contact(
+            &aabb(Vec2::ZERO, Vec2::splat(10.)),
+            &aabb(Vec2::new(15., 3.), Vec2::splat(10.)),
+        )
+        .unwrap();
+        assert_close(c.normal, Vec2::X);
+        assert!((c.penetration - 5.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn normal_points_from_a_to_b() {
+        let shallow = contact(&circle(Vec2::ZERO, 10.), &circle(Vec2::new(0., -15.), 10.));
+        assert_close(shallow.unwrap().normal, -Vec2::Y);
+
+        let deep = contact(
+            &aabb(Vec2::ZERO, Vec2::splat(10.)),
+            &aabb(Vec2::new(-15., 3.), Vec2::splat(10.)),
+        );
+        assert_close(deep.unwrap().normal, -Vec2::X);
+    }
+
+    #[test]
+    fn stacked_cores_still_get_a_normal() {
+        let c = contact(&circle(Vec2::ZERO, 10.), &circle(Vec2::ZERO, 10.)).unwrap();
+        assert!(c.normal.is_normalized());
+    }
+
+    #[test]
+    fn separation_measures_the_gap() {
+        let (distance, point, normal) =
+            separation(&circle(Vec2::ZERO, 10.), &circle(Vec2::new(30., 0.), 5.)).unwrap();
+        assert!((distance - 15.).abs() < 1e-3);
+        assert_close(point, Vec2::new(25., 0.));
+        assert_close(normal, Vec2::X);
+
+        assert!(separation(&circle(Vec2::ZERO, 10.), &circle(Vec2::new(12., 0.), 5.)).is_none());
+    }
+
+    #[test]
+    fn sweep_stops_at_the_first_touch() {
+        let (distance, point, normal) = sweep(
+            &circle(Vec2::ZERO, 5.),
+            Vec2::X,
+            100.,
+            &circle(Vec2::new(50., 0.), 5.),
+        )
+        .unwrap();
+        assert!((distance - 40.).abs() < 1e-2);
+        assert_close(point, Vec2::new(45., 0.));
+        assert_close(normal, -Vec2::X);
+    }
+
+    #[test]
+    fn sweep_into_capsule() {
+        let (distance, point, normal) = sweep(
+            &circle(Vec2::new(0., 50.), 5.),
+            -Vec2::Y,
+            100.,
+            &capsule(Vec2::new(-20., 0.), Vec2::new(20., 0.), 5.),
+        )
+        .unwrap();
+        assert!((distance - 40.).abs() < 1e-2);
+        assert_close(point, Vec2::new(0., 5.));
+        assert_close(normal, Vec2::Y);
+    }
+
+    #[test]
+    fn sweep_into_sector() {
+        let (distance, point, normal) = sweep(
+            &aabb(Vec2::new(50., 0.), Vec2::splat(5.)),
+            -Vec2::X,
+            100.,
+            &sector(Vec2::ZERO, 20., Vec2::X, FRAC_PI_4),
+        )
+        .unwrap();
+        assert!((distance - 25.).abs() < 1e-2);
+        assert_within(point, Vec2::new(20., 0.), 1e-2);
+        assert_close(normal, Vec2::X);
+    }
+
+    #[test]
+    fn sweep_into_the_gap_of_a_wide_sector() {
+        // Slides right into the gap until it wedges between both edges, rather than stopping at
+        // the chord across it
+        let (distance, point, normal) = Collider::circle(3.)
+            .sweep(
+                &Transform::from_xyz(-40., 0., 0.),
+                Vec2::X,
+                100.,
+                &Collider::sector(20., 1.5 * PI),
+                &Transform::IDENTITY,
+            )
+            .unwrap();
+        let stop = 3. * 2_f32.sqrt();
+        assert!((distance - (40. - stop)).abs() < 1e-2);
+        assert!((point.x + stop / 2.).abs() < 1e-2);
+        assert!((normal.x + 1. / 2_f32.sqrt()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sweep_misses() {
+        let a = circle(Vec2::ZERO, 5.);
+
+        // Passes by to the side
+        assert!(sweep(&a, Vec2::X, 100., &circle(Vec2::new(50., 20.), 5.)).is_none());
+        // Out of reach
+        assert!(sweep(&a, Vec2::X, 100., &circle(Vec2::new(200., 0.), 5.)).is_none());
+        // Moving away
+        assert!(sweep(&a, -Vec2::X, 100., &circle(Vec2::new(50., 0.), 5.)).is_none());
+    }
+}
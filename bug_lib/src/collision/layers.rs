@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+/// Bits for each collision layer, combine them with `|`
+pub struct Layer;
+
+impl Layer {
+    pub const NONE: u32 = 0;
+    pub const ENEMY: u32 = 1 << 0;
+    pub const TOWER: u32 = 1 << 1;
+    pub const SWATTER: u32 = 1 << 2;
+    pub const PICKUP: u32 = 1 << 3;
+    pub const PROJECTILE: u32 = 1 << 4;
+    pub const OBSTACLE: u32 = 1 << 5;
+    pub const ALL: u32 = u32::MAX;
+}
+
+/// Which layers a collider is on, and which layers it can collide with.
+///
+/// Two colliders only interact if each one is on a layer the other is looking for.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub membership: u32,
+    pub filter: u32,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        CollisionLayers::new(Layer::ALL, Layer::ALL)
+    }
+}
+
+impl CollisionLayers {
+    pub const fn new(membership: u32, filter: u32) -> Self {
+        CollisionLayers { membership, filter }
+    }
+
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        (self.membership & other.filter) != 0 && (other.membership & self.filter) != 0
+    }
+}
@@ -1,30 +1,186 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
 use bevy::prelude::*;
 
-use crate::{movement::velocity_moves_transforms, swatter::swatter_follows_mouse};
+use crate::movement::MovementSet;
+
+use self::prelude::{
+    detect_collisions, rebuild_spatial_hash, separate_colliders, Colliding, CollisionEnded,
+    CollisionStarted, Collisions, Contact, SeparationConfig, SpatialHash,
+};
+
+use self::gjk::{Convex, Core};
+
+mod events;
+mod gjk;
+mod layers;
+mod query;
+mod separation;
+mod spatial_hash;
+
+pub mod prelude {
+    pub use super::events::*;
+    pub use super::layers::*;
+    pub use super::query::*;
+    pub use super::separation::*;
+    pub use super::spatial_hash::*;
+}
 
+/// Finds collisions once per fixed tick.
+///
+/// The `SpatialHash`, `Collisions` and the collision events are rebuilt in `CollisionSet`, right
+/// after things have moved in `FixedUpdate`, and anything in `FixedUpdate` that reads them goes
+/// after it. `Update` sees them as they were after the last tick of the frame.
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        /*
-        app
-        .add_systems(
-            Update,
-            visualize_colliders
-                .after(swatter_follows_mouse)
-                .after(velocity_moves_transforms),
-        )
-        */
+        app.init_resource::<SpatialHash>()
+            .init_resource::<Collisions>()
+            .init_resource::<SeparationConfig>()
+            .add_event::<CollisionStarted>()
+            .add_event::<Colliding>()
+            .add_event::<CollisionEnded>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    (rebuild_spatial_hash, detect_collisions)
+                        .chain()
+                        .in_set(CollisionSet),
+                    separate_colliders.in_set(MovementSet::Resolve),
+                ),
+            );
     }
 }
 
-/// Collider for collision detection (sorry, only circles because game jam)
-#[derive(Component, Default, Clone)]
-pub struct Collider {
-    pub radius: f32,
+/// Rebuilds the `SpatialHash` and `Collisions`, and sends the collision events
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CollisionSet;
+
+/// Collider for collision detection. Capsules and sectors turn with the entity, boxes don't.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum Collider {
+    Circle {
+        radius: f32,
+    },
+    /// Box lined up with the world axes
+    Aabb {
+        half_extents: Vec2,
+    },
+    /// Rounded line running along the local x axis
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+    /// Slice of a circle pointing down the local x axis, with its point at the entity's position
+    Sector {
+        radius: f32,
+        /// How far the slice opens either side of its middle, up to `PI` for a full circle
+        half_angle: f32,
+    },
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Collider::circle(0.)
+    }
 }
 
 impl Collider {
+    pub fn circle(radius: f32) -> Self {
+        Collider::Circle { radius }
+    }
+
+    pub fn aabb(size: Vec2) -> Self {
+        Collider::Aabb {
+            half_extents: size / 2.,
+        }
+    }
+
+    pub fn capsule(length: f32, radius: f32) -> Self {
+        Collider::Capsule {
+            half_length: length / 2.,
+            radius,
+        }
+    }
+
+    pub fn sector(radius: f32, angle: f32) -> Self {
+        Collider::Sector {
+            radius,
+            half_angle: (angle / 2.).clamp(0., PI),
+        }
+    }
+
+    /// Radius of a circle around the entity's position that the whole shape fits in
+    pub fn bounding_radius(&self) -> f32 {
+        match *self {
+            Collider::Circle { radius } => radius,
+            Collider::Aabb { half_extents } => half_extents.length(),
+            Collider::Capsule {
+                half_length,
+                radius,
+            } => half_length + radius,
+            Collider::Sector { radius, .. } => radius,
+        }
+    }
+
+    /// Breaks the shape into convex pieces in world space. Sectors wider than a half circle
+    /// aren't convex, so they get split down the middle.
+    fn convex_pieces(&self, transform: &Transform) -> ([Convex; 2], usize) {
+        let position = transform.translation.xy();
+        let direction = (transform.rotation * Vec3::X).xy().normalize_or_zero();
+        let direction = if direction == Vec2::ZERO {
+            Vec2::X
+        } else {
+            direction
+        };
+
+        let single = |core, margin| ([Convex::new(core, margin); 2], 1);
+        match *self {
+            Collider::Circle { radius } => single(Core::Point(position), radius),
+            Collider::Aabb { half_extents } => single(
+                Core::Aabb {
+                    center: position,
+                    half_extents,
+                },
+                0.,
+            ),
+            Collider::Capsule {
+                half_length,
+                radius,
+            } => single(
+                Core::Segment(
+                    position - direction * half_length,
+                    position + direction * half_length,
+                ),
+                radius,
+            ),
+            Collider::Sector { radius, half_angle } if half_angle <= FRAC_PI_2 => single(
+                Core::Sector {
+                    apex: position,
+                    radius,
+                    direction,
+                    half_angle,
+                },
+                0.,
+            ),
+            Collider::Sector { radius, half_angle } => {
+                let half = |side: f32| {
+                    Convex::new(
+                        Core::Sector {
+                            apex: position,
+                            radius,
+                            direction: Vec2::from_angle(side * half_angle / 2.).rotate(direction),
+                            half_angle: half_angle / 2.,
+                        },
+                        0.,
+                    )
+                };
+                ([half(1.), half(-1.)], 2)
+            }
+        }
+    }
+
     // Check if this collider collides with another
     pub fn collides_with(
         &self,
@@ -32,20 +188,138 @@ impl Collider {
         other: &Collider,
         other_transform: &Transform,
     ) -> bool {
-        let distance = self_transform
-            .translation
-            .xy()
-            .distance(other_transform.translation.xy());
-        let radii = self.radius + other.radius;
+        self.contact(self_transform, other, other_transform)
+            .is_some()
+    }
+
+    /// Where this collider overlaps another, if it does
+    pub fn contact(
+        &self,
+        self_transform: &Transform,
+        other: &Collider,
+        other_transform: &Transform,
+    ) -> Option<Contact> {
+        let a = self_transform.translation.xy();
+        let b = other_transform.translation.xy();
+
+        // Nothing can touch if the bounding circles don't
+        let distance = a.distance(b);
+        if distance > self.bounding_radius() + other.bounding_radius() {
+            return None;
+        }
+
+        // Circles are by far the most common, so skip the general case for them
+        if let (Collider::Circle { radius: ra }, Collider::Circle { radius: rb }) = (self, other) {
+            let penetration = ra + rb - distance;
+
+            // Perfectly stacked colliders still need to be pushed apart some way
+            let normal = (b - a).try_normalize().unwrap_or(Vec2::X);
+            return Some(Contact {
+                point: a + normal * (ra - penetration / 2.),
+                normal,
+                penetration,
+            });
+        }
 
-        distance <= radii
+        // With a piece of a split sector, the deepest overlap is the one that matters
+        let (pieces_a, count_a) = self.convex_pieces(self_transform);
+        let (pieces_b, count_b) = other.convex_pieces(other_transform);
+        pieces_a[..count_a]
+            .iter()
+            .flat_map(|pa| pieces_b[..count_b].iter().map(move |pb| (pa, pb)))
+            .filter_map(|(pa, pb)| gjk::contact(pa, pb))
+            .max_by(|x, y| x.penetration.total_cmp(&y.penetration))
+    }
+
+    /// Slides this collider along a unit direction until it hits another, returning how far it
+    /// went, where it hit and the other collider's surface normal there
+    pub fn sweep(
+        &self,
+        self_transform: &Transform,
+        direction: Vec2,
+        max_distance: f32,
+        other: &Collider,
+        other_transform: &Transform,
+    ) -> Option<(f32, Vec2, Vec2)> {
+        // Skip anything the bounding circles can't reach
+        let start = self_transform.translation.xy();
+        let end = start + direction * max_distance;
+        let target = other_transform.translation.xy();
+        let reach = self.bounding_radius() + other.bounding_radius();
+        if distance_to_segment(target, start, end) > reach {
+            return None;
+        }
+
+        let (pieces_a, count_a) = self.convex_pieces(self_transform);
+        let (pieces_b, count_b) = other.convex_pieces(other_transform);
+        pieces_a[..count_a]
+            .iter()
+            .flat_map(|pa| pieces_b[..count_b].iter().map(move |pb| (pa, pb)))
+            .filter_map(|(pa, pb)| gjk::sweep(pa, direction, max_distance, pb))
+            .min_by(|x, y| x.0.total_cmp(&y.0))
     }
 }
 
+/// How far a point is from the closest point on a line segment
+pub fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let line = end - start;
+    let t = if line.length_squared() > f32::EPSILON {
+        ((point - start).dot(line) / line.length_squared()).clamp(0., 1.)
+    } else {
+        0.
+    };
+    point.distance(start + line * t)
+}
+
 pub fn visualize_colliders(q: Query<(&Collider, &Transform)>, mut gizmos: Gizmos) {
     for (c, t) in q.iter() {
-        gizmos
-            .circle_2d(t.translation.xy(), c.radius, Color::ORANGE)
-            .segments(64);
+        let position = t.translation.xy();
+        let direction = (t.rotation * Vec3::X).xy().normalize_or_zero();
+        match *c {
+            Collider::Circle { radius } => {
+                gizmos
+                    .circle_2d(position, radius, Color::ORANGE)
+                    .segments(64);
+            }
+            Collider::Aabb { half_extents } => {
+                gizmos.rect_2d(position, 0., half_extents * 2., Color::ORANGE);
+            }
+            Collider::Capsule {
+                half_length,
+                radius,
+            } => {
+                let along = direction * half_length;
+                let across = direction.perp() * radius;
+                gizmos.line_2d(
+                    position - along + across,
+                    position + along + across,
+                    Color::ORANGE,
+                );
+                gizmos.line_2d(
+                    position - along - across,
+                    position + along - across,
+                    Color::ORANGE,
+                );
+
+                // Gizmo arcs measure their angle clockwise from up
+                let angle = FRAC_PI_2 - direction.y.atan2(direction.x);
+                gizmos
+                    .arc_2d(position + along, angle, PI, radius, Color::ORANGE)
+                    .segments(32);
+                gizmos
+                    .arc_2d(position - along, angle + PI, PI, radius, Color::ORANGE)
+                    .segments(32);
+            }
+            Collider::Sector { radius, half_angle } => {
+                let angle = FRAC_PI_2 - direction.y.atan2(direction.x);
+                gizmos
+                    .arc_2d(position, angle, half_angle * 2., radius, Color::ORANGE)
+                    .segments(64);
+                for side in [-1., 1.] {
+                    let edge = Vec2::from_angle(side * half_angle).rotate(direction) * radius;
+                    gizmos.line_2d(position, position + edge, Color::ORANGE);
+                }
+            }
+        }
     }
 }
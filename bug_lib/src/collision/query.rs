@@ -0,0 +1,266 @@
+use bevy::{
+    ecs::{query::ReadOnlyWorldQuery, system::SystemParam},
+    prelude::*,
+};
+
+use super::{
+    prelude::{ColliderQuery, CollisionLayers, SpatialHash},
+    Collider,
+};
+
+/// Something found by a `SpatialQuery`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialHit {
+    pub entity: Entity,
+    /// How far the cast went before hitting, or how far apart the centers are for overlaps
+    pub distance: f32,
+    pub point: Vec2,
+    /// Surface normal of the thing that got hit, pointing back towards the query
+    pub normal: Vec2,
+}
+
+/// Ray casts, shape casts and overlap queries against every collider matching `F`.
+///
+/// Results are sorted nearest first, and only include colliders that can interact with the
+/// given layers. Use `CollisionLayers::default()` to hit everything.
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's, F: ReadOnlyWorldQuery + 'static = ()> {
+    hash: Res<'w, SpatialHash>,
+    colliders: ColliderQuery<'w, 's, F>,
+}
+
+impl<'w, 's, F: ReadOnlyWorldQuery + 'static> SpatialQuery<'w, 's, F> {
+    /// Everything a line starting at `origin` passes through
+    pub fn cast_ray(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Vec<SpatialHit> {
+        self.cast_circle(origin, 0., direction, max_distance, layers)
+    }
+
+    /// Everything a circle moving from `origin` runs into
+    pub fn cast_circle(
+        &self,
+        origin: Vec2,
+        radius: f32,
+        direction: Vec2,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Vec<SpatialHit> {
+        self.cast_shape(
+            &Collider::circle(radius),
+            &Transform::from_translation(origin.extend(0.)),
+            direction,
+            max_distance,
+            layers,
+        )
+    }
+
+    /// Everything a collider runs into as it slides along `direction`
+    pub fn cast_shape(
+        &self,
+        collider: &Collider,
+        transform: &Transform,
+        direction: Vec2,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Vec<SpatialHit> {
+        let Some(direction) = direction.try_normalize() else {
+            return Vec::new();
+        };
+
+        let start = transform.translation.xy();
+        let end = start + direction * max_distance;
+        let mut hits: Vec<SpatialHit> = self
+            .hash
+            .query_segment(start, end, collider.bounding_radius())
+            .filter(|entry| entry.layers.interacts_with(&layers))
+            .filter_map(|entry| {
+                let (entity, other, other_transform, _) = self.colliders.get(entry.entity).ok()?;
+                let (distance, point, normal) =
+                    collider.sweep(transform, direction, max_distance, other, other_transform)?;
+                Some(SpatialHit {
+                    entity,
+                    distance,
+                    point,
+                    normal,
+                })
+            })
+            .collect();
+
+        sort_hits(&mut hits);
+        hits
+    }
+
+    /// The first thing a line starting at `origin` hits, handy for line of sight checks
+    pub fn first_ray_hit(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Option<SpatialHit> {
+        self.cast_ray(origin, direction, max_distance, layers)
+            .into_iter()
+            .next()
+    }
+
+    /// Everything touching a circle
+    pub fn overlap_circle(
+        &self,
+        position: Vec2,
+        radius: f32,
+        layers: CollisionLayers,
+    ) -> Vec<SpatialHit> {
+        self.overlap_shape(
+            &Collider::circle(radius),
+            &Transform::from_translation(position.extend(0.)),
+            layers,
+        )
+    }
+
+    /// Everything touching a collider, like a `Collider::sector` to find what's in a cone
+    pub fn overlap_shape(
+        &self,
+        collider: &Collider,
+        transform: &Transform,
+        layers: CollisionLayers,
+    ) -> Vec<SpatialHit> {
+        let position = transform.translation.xy();
+        let mut hits: Vec<SpatialHit> = self
+            .hash
+            .query_circle_with_layers(position, collider.bounding_radius(), layers)
+            .filter_map(|entry| {
+                let (entity, other, other_transform, _) = self.colliders.get(entry.entity).ok()?;
+                let contact = collider.contact(transform, other, other_transform)?;
+                Some(SpatialHit {
+                    entity,
+                    distance: position.distance(other_transform.translation.xy()),
+                    point: contact.point,
+                    normal: -contact.normal,
+                })
+            })
+            .collect();
+
+        sort_hits(&mut hits);
+        hits
+    }
+}
+
+fn sort_hits(hits: &mut [SpatialHit]) {
+    hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use crate::collision::prelude::Layer;
+
+    use super::*;
+
+    /// Spawns circles and builds the spatial hash for them by hand
+    fn world_with(circles: &[(Vec2, f32, CollisionLayers)]) -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        let mut hash = SpatialHash::new(32.);
+        let entities = circles
+            .iter()
+            .map(|&(position, radius, layers)| {
+                let e = world
+                    .spawn((
+                        Collider::circle(radius),
+                        Transform::from_translation(position.extend(0.)),
+                        layers,
+                    ))
+                    .id();
+                hash.insert(e, position, radius, layers);
+                e
+            })
+            .collect();
+        world.insert_resource(hash);
+        (world, entities)
+    }
+
+    #[test]
+    fn ray_hits_are_sorted_nearest_first() {
+        let all = CollisionLayers::default();
+        let (mut world, e) = world_with(&[
+            (Vec2::new(100., 0.), 5., all),
+            (Vec2::new(50., 0.), 5., all),
+            (Vec2::new(50., 50.), 5., all),
+        ]);
+        let mut state = SystemState::<SpatialQuery>::new(&mut world);
+        let query = state.get(&world);
+
+        let hits = query.cast_ray(Vec2::ZERO, Vec2::X, 200., all);
+        assert_eq!(
+            hits.iter().map(|h| h.entity).collect::<Vec<_>>(),
+            vec![e[1], e[0]]
+        );
+        assert!((hits[0].distance - 45.).abs() < 1e-2);
+        assert!(hits[0].normal.distance(-Vec2::X) < 1e-3);
+
+        assert!(query.cast_ray(Vec2::ZERO, Vec2::X, 40., all).is_empty());
+        assert!(query.cast_ray(Vec2::ZERO, -Vec2::X, 200., all).is_empty());
+    }
+
+    #[test]
+    fn ray_skips_layers_it_cant_interact_with() {
+        let enemy = CollisionLayers::new(Layer::ENEMY, Layer::ALL);
+        let obstacle = CollisionLayers::new(Layer::OBSTACLE, Layer::ALL);
+        let (mut world, e) = world_with(&[
+            (Vec2::new(50., 0.), 5., obstacle),
+            (Vec2::new(100., 0.), 5., enemy),
+        ]);
+        let mut state = SystemState::<SpatialQuery>::new(&mut world);
+        let query = state.get(&world);
+
+        let looking_for_enemies = CollisionLayers::new(Layer::ALL, Layer::ENEMY);
+        let hit = query.first_ray_hit(Vec2::ZERO, Vec2::X, 200., looking_for_enemies);
+        assert_eq!(hit.map(|h| h.entity), Some(e[1]));
+    }
+
+    #[test]
+    fn shape_cast_is_wider_than_a_ray() {
+        let all = CollisionLayers::default();
+        let (mut world, e) = world_with(&[(Vec2::new(50., 8.), 5., all)]);
+        let mut state = SystemState::<SpatialQuery>::new(&mut world);
+        let query = state.get(&world);
+
+        assert!(query.cast_ray(Vec2::ZERO, Vec2::X, 100., all).is_empty());
+
+        let hits = query.cast_shape(
+            &Collider::aabb(Vec2::splat(20.)),
+            &Transform::IDENTITY,
+            Vec2::X,
+            100.,
+            all,
+        );
+        assert_eq!(
+            hits.iter().map(|h| h.entity).collect::<Vec<_>>(),
+            vec![e[0]]
+        );
+        assert!((hits[0].distance - 35.).abs() < 1e-2);
+    }
+
+    #[test]
+    fn overlap_finds_only_what_touches() {
+        let all = CollisionLayers::default();
+        let (mut world, e) = world_with(&[
+            (Vec2::new(20., 0.), 5., all),
+            (Vec2::new(0., -12.), 5., all),
+            (Vec2::new(40., 0.), 5., all),
+        ]);
+        let mut state = SystemState::<SpatialQuery>::new(&mut world);
+        let query = state.get(&world);
+
+        let hits = query.overlap_circle(Vec2::ZERO, 20., all);
+        assert_eq!(
+            hits.iter().map(|h| h.entity).collect::<Vec<_>>(),
+            vec![e[1], e[0]]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::movement::Mass;
+
+use super::{prelude::Collisions, Collider};
+
+/// Marks a collider that gets gently pushed out of other colliders with this marker
+#[derive(Component, Default, Clone, Copy)]
+pub struct Separation;
+
+/// How hard overlapping colliders with `Separation` get pushed apart
+#[derive(Resource)]
+pub struct SeparationConfig {
+    /// Fraction of the overlap resolved each second, higher is stiffer
+    pub strength: f32,
+    /// Fastest a collider can be pushed, in pixels per second, so big pile ups don't explode
+    pub max_speed: f32,
+}
+
+impl Default for SeparationConfig {
+    fn default() -> Self {
+        SeparationConfig {
+            strength: 8.,
+            max_speed: 200.,
+        }
+    }
+}
+
+/// Pushes overlapping pairs apart along their contact normal, heavier colliders moving less
+pub fn separate_colliders(
+    collisions: Res<Collisions>,
+    config: Res<SeparationConfig>,
+    mut q: Query<(&mut Transform, &Collider, Option<&Mass>), With<Separation>>,
+    time: Res<Time>,
+) {
+    let resolve = (config.strength * time.delta_seconds()).min(1.);
+    let max_push = config.max_speed * time.delta_seconds();
+
+    for (a, b, _) in collisions.iter() {
+        let Ok([(mut at, ac, am), (mut bt, bc, bm)]) = q.get_many_mut([a, b]) else {
+            continue;
+        };
+        // Pairs earlier in the list may have pushed them already
+        let Some(contact) = ac.contact(&at, bc, &bt) else {
+            continue;
+        };
+
+        let am = am.map_or(1., |m| m.0).max(f32::EPSILON);
+        let bm = bm.map_or(1., |m| m.0).max(f32::EPSILON);
+        let push = (contact.penetration * resolve).min(max_push);
+
+        // Each side moves by the other's share of the total mass
+        let total = am + bm;
+        let offset = contact.normal * push;
+        at.translation -= (offset * bm / total).extend(0.);
+        bt.translation += (offset * am / total).extend(0.);
+    }
+}
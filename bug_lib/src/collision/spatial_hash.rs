@@ -0,0 +1,171 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use bevy::{ecs::query::ReadOnlyWorldQuery, prelude::*, utils::HashMap};
+
+use super::{distance_to_segment, prelude::CollisionLayers, Collider};
+
+/// A collider's position in the spatial hash as of the last rebuild
+#[derive(Debug, Clone, Copy)]
+pub struct SpatialEntry {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub radius: f32,
+    pub layers: CollisionLayers,
+}
+
+/// Everything needed to check a collider against the spatial hash
+pub type ColliderQuery<'w, 's, F = ()> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Collider,
+        &'static Transform,
+        Option<&'static CollisionLayers>,
+    ),
+    F,
+>;
+
+/// Uniform grid of colliders, rebuilt every fixed tick in `CollisionSet`, used to find nearby
+/// colliders without checking every single one.
+///
+/// Each collider is stored in the cell containing its center, and queries are widened by the
+/// largest radius in the grid so nothing is missed.
+#[derive(Resource)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<SpatialEntry>>,
+    max_radius: f32,
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        SpatialHash::new(64.)
+    }
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialHash {
+            cell_size,
+            cells: HashMap::default(),
+            max_radius: 0.,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // Keep the allocations around for the next rebuild
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.max_radius = 0.;
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2, radius: f32, layers: CollisionLayers) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(SpatialEntry {
+            entity,
+            position,
+            radius,
+            layers,
+        });
+        self.max_radius = self.max_radius.max(radius);
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Every collider overlapping the given circle
+    pub fn query_circle(
+        &self,
+        position: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = &SpatialEntry> + '_ {
+        let reach = Vec2::splat(radius + self.max_radius);
+        let min = self.cell(position - reach);
+        let max = self.cell(position + reach);
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |entry| entry.position.distance(position) <= radius + entry.radius)
+    }
+
+    /// Every collider that might touch a circle swept along a line segment
+    pub fn query_segment(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = &SpatialEntry> + '_ {
+        let reach = radius + self.max_radius;
+        let min = self.cell(start.min(end) - Vec2::splat(reach));
+        let max = self.cell(start.max(end) + Vec2::splat(reach));
+
+        // Only visit the cells near the line, not the whole box around it
+        let cell_reach = reach + self.cell_size * FRAC_1_SQRT_2;
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter(move |cell| {
+                let center = (cell.as_vec2() + 0.5) * self.cell_size;
+                distance_to_segment(center, start, end) <= cell_reach
+            })
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |entry| {
+                distance_to_segment(entry.position, start, end) <= radius + entry.radius
+            })
+    }
+
+    /// Every collider overlapping the given circle that can interact with the given layers
+    pub fn query_circle_with_layers(
+        &self,
+        position: Vec2,
+        radius: f32,
+        layers: CollisionLayers,
+    ) -> impl Iterator<Item = &SpatialEntry> + '_ {
+        self.query_circle(position, radius)
+            .filter(move |entry| entry.layers.interacts_with(&layers))
+    }
+
+    /// Every colliding pair of entities where the first comes from `a` and the second from `b`,
+    /// as long as their collision layers interact
+    pub fn pairs_between<A: ReadOnlyWorldQuery, B: ReadOnlyWorldQuery>(
+        &self,
+        a: &ColliderQuery<A>,
+        b: &ColliderQuery<B>,
+    ) -> Vec<(Entity, Entity)> {
+        let mut pairs = Vec::new();
+        for (ae, ac, at, al) in a.iter() {
+            let layers = al.copied().unwrap_or_default();
+            for entry in
+                self.query_circle_with_layers(at.translation.xy(), ac.bounding_radius(), layers)
+            {
+                let Ok((be, bc, bt, _)) = b.get(entry.entity) else {
+                    continue;
+                };
+
+                // The grid only knows bounding circles, so check the actual shapes
+                if ae != be && ac.collides_with(at, bc, bt) {
+                    pairs.push((ae, be));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// Refills the spatial hash with every collider
+pub fn rebuild_spatial_hash(mut hash: ResMut<SpatialHash>, q: ColliderQuery) {
+    hash.clear();
+    for (e, c, t, l) in q.iter() {
+        hash.insert(
+            e,
+            t.translation.xy(),
+            c.bounding_radius(),
+            l.copied().unwrap_or_default(),
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{prelude::DamageKind, read_damage_events};
+
+pub struct CooldownPlugin;
+
+impl Plugin for CooldownPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HitCooldowns>().add_systems(
+            Update,
+            (invulnerability_tick, hit_cooldowns_tick).before(read_damage_events),
+        );
+    }
+}
+
+/// Ignores all damage until the timer runs out
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+impl Invulnerable {
+    pub fn new(duration: Duration) -> Self {
+        Invulnerable(Timer::new(duration, TimerMode::Once))
+    }
+}
+
+/// Makes this entity `Invulnerable` for a while every time a hit lands on it
+#[derive(Component, Clone)]
+pub struct InvulnerableAfterHit(pub Duration);
+
+/// How long this entity has to wait before it can damage the same target again
+#[derive(Component, Default, Clone)]
+pub struct DamageCooldown(pub Duration);
+
+/// Cooldowns for every (attacker, target, damage kind) that has recently landed a hit. Keeping
+/// kinds apart means damage over time credited to an attacker isn't held back by its direct hits
+#[derive(Resource, Default)]
+pub struct HitCooldowns(pub HashMap<(Entity, Entity, DamageKind), Timer>);
+
+impl HitCooldowns {
+    pub fn is_cooling_down(&self, attacker: Entity, target: Entity, kind: DamageKind) -> bool {
+        self.0.contains_key(&(attacker, target, kind))
+    }
+
+    pub fn start(
+        &mut self,
+        attacker: Entity,
+        target: Entity,
+        kind: DamageKind,
+        duration: Duration,
+    ) {
+        self.0.insert(
+            (attacker, target, kind),
+            Timer::new(duration, TimerMode::Once),
+        );
+    }
+}
+
+fn invulnerability_tick(
+    mut q: Query<(Entity, &mut Invulnerable)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (e, mut i) in q.iter_mut() {
+        i.0.tick(time.delta());
+
+        if i.0.finished() {
+            commands.entity(e).remove::<Invulnerable>();
+        }
+    }
+}
+
+fn hit_cooldowns_tick(mut cooldowns: ResMut<HitCooldowns>, time: Res<Time>) {
+    cooldowns.0.retain(|_, timer| {
+        timer.tick(time.delta());
+        !timer.finished()
+    });
+}
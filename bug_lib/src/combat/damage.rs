@@ -0,0 +1,65 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng;
+use serde::Deserialize;
+
+/// The different kinds of damage that can be dealt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum DamageKind {
+    #[default]
+    Blunt,
+    Piercing,
+    Fire,
+    Poison,
+    Electric,
+}
+
+impl DamageKind {
+    /// The color used when showing this kind of damage
+    pub fn color(&self) -> Color {
+        match self {
+            DamageKind::Blunt => Color::WHITE,
+            DamageKind::Piercing => Color::SILVER,
+            DamageKind::Fire => Color::ORANGE_RED,
+            DamageKind::Poison => Color::LIME_GREEN,
+            DamageKind::Electric => Color::CYAN,
+        }
+    }
+}
+
+/// How much of each kind of damage an entity shrugs off.
+///
+/// A value of `0.5` halves incoming damage of that kind, `1.0` makes the entity immune
+/// and negative values make it take extra damage.
+#[derive(Component, Default, Clone, Debug)]
+pub struct Resistances(pub HashMap<DamageKind, f32>);
+
+impl Resistances {
+    /// Scales an amount of damage by the resistance to its kind
+    pub fn apply(&self, kind: DamageKind, amount: f32) -> f32 {
+        let resistance = self.0.get(&kind).copied().unwrap_or(0.);
+        (amount * (1. - resistance)).max(0.)
+    }
+}
+
+/// Gives damage dealt by this entity a chance to be multiplied
+#[derive(Component, Clone, Debug)]
+pub struct CriticalStrike {
+    /// From 0 to 1
+    pub chance: f32,
+    pub multiplier: f32,
+}
+
+impl Default for CriticalStrike {
+    fn default() -> Self {
+        CriticalStrike {
+            chance: 0.,
+            multiplier: 2.,
+        }
+    }
+}
+
+impl CriticalStrike {
+    pub fn roll(&self, rng: &mut impl Rng) -> bool {
+        rng.gen_bool(self.chance.clamp(0., 1.) as f64)
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::{prelude::*, text::Text2dBounds};
+use rand::prelude::*;
+
+use crate::asset_loading::AppAssets;
+
+use super::{read_damage_events, DamageDealtEvent};
+
+pub struct DamageNumbersPlugin;
+
+impl Plugin for DamageNumbersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_damage_numbers.after(read_damage_events),
+                damage_numbers_float,
+            ),
+        );
+    }
+}
+
+/// World space text showing how much damage something took, rises and fades out
+#[derive(Component)]
+pub struct DamageNumber {
+    pub timer: Timer,
+    pub velocity: Vec2,
+}
+
+fn spawn_damage_numbers(
+    mut evr: EventReader<DamageDealtEvent>,
+    tq: Query<&Transform>,
+    mut commands: Commands,
+    assets: Res<AppAssets>,
+) {
+    let mut rng = thread_rng();
+    for e in evr.read() {
+        // Nothing worth showing
+        if e.amount < 0.5 {
+            continue;
+        }
+
+        let Ok(t) = tq.get(e.target) else {
+            continue;
+        };
+
+        let (text, font_size, color) = if e.crit {
+            (format!("{:.0}!", e.amount), 40., Color::GOLD)
+        } else {
+            (format!("{:.0}", e.amount), 28., e.kind.color())
+        };
+
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: assets.font.clone_weak(),
+                        font_size,
+                        color,
+                    },
+                ),
+                text_2d_bounds: Text2dBounds::UNBOUNDED,
+                transform: Transform::from_xyz(
+                    t.translation.x + rng.gen_range(-16.0..16.0),
+                    t.translation.y + 16.,
+                    10.,
+                ),
+                ..Default::default()
+            },
+            DamageNumber {
+                timer: Timer::from_seconds(0.75, TimerMode::Once),
+                velocity: Vec2::new(rng.gen_range(-20.0..20.0), 80.),
+            },
+        ));
+    }
+}
+
+fn damage_numbers_float(
+    mut q: Query<(Entity, &mut DamageNumber, &mut Transform, &mut Text)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (e, mut dn, mut t, mut text) in q.iter_mut() {
+        dn.timer.tick(time.delta());
+
+        t.translation += (dn.velocity * time.delta_seconds()).extend(0.);
+
+        let alpha = 1. - dn.timer.percent();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+
+        if dn.timer.finished() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
@@ -1,10 +1,14 @@
+use std::time::Duration;
+
 use bevy::{prelude::*, time::Stopwatch, transform::commands};
 
+use super::{read_heal_events, Dying, HealEvent};
+
 pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, flashers_tick);
+        app.add_systems(Update, regeneration_tick.before(read_heal_events));
     }
 }
 
@@ -17,23 +21,57 @@ impl Default for Health {
     }
 }
 
-/// Flashes a sprite white and back to it's original color
+/// The most health something can be healed up to
+#[derive(Component)]
+pub struct MaxHealth(pub f32);
+
+impl Default for MaxHealth {
+    fn default() -> Self {
+        MaxHealth(100.)
+    }
+}
+
+impl MaxHealth {
+    /// How full the health is, from 0 to 1
+    pub fn fraction(&self, health: &Health) -> f32 {
+        if self.0 <= 0. {
+            return 0.;
+        }
+        (health.0 / self.0).clamp(0., 1.)
+    }
+}
+
+/// Heals over time once the entity has gone a while without taking damage
 #[derive(Component)]
-pub struct Flasher(pub Timer);
+pub struct Regeneration {
+    pub per_second: f32,
+    /// Restarted whenever the entity takes damage
+    pub delay: Timer,
+}
 
-fn flashers_tick(
-    mut fq: Query<(Entity, &mut Flasher, &mut Sprite)>,
+impl Regeneration {
+    pub fn new(per_second: f32, delay: Duration) -> Self {
+        Regeneration {
+            per_second,
+            delay: Timer::new(delay, TimerMode::Once),
+        }
+    }
+}
+
+fn regeneration_tick(
+    mut q: Query<(Entity, &mut Regeneration), Without<Dying>>,
     time: Res<Time>,
-    mut commands: Commands,
+    mut hewr: EventWriter<HealEvent>,
 ) {
-    for (e, mut f, mut s) in fq.iter_mut() {
-        f.0.tick(time.delta());
-
-        s.color = Color::rgba(255., 255., 255., 1.);
+    for (e, mut r) in q.iter_mut() {
+        r.delay.tick(time.delta());
 
-        if f.0.finished() {
-            s.color = Color::WHITE;
-            commands.entity(e).remove::<Flasher>();
+        if r.delay.finished() {
+            hewr.send(HealEvent {
+                amount: r.per_second * time.delta_seconds(),
+                target: e,
+                source: None,
+            });
         }
     }
 }
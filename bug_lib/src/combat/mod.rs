@@ -1,20 +1,44 @@
 use bevy::{prelude::*, transform::commands};
+use rand::thread_rng;
 
-use self::prelude::{Health, HealthPlugin};
+use self::prelude::{
+    Armor, CooldownPlugin, CriticalStrike, DamageCooldown, DamageKind, DamageNumbersPlugin, Health,
+    HealthPlugin, HitCooldowns, Invulnerable, InvulnerableAfterHit, MaxHealth, Regeneration,
+    Resistances, Shield, ShieldPlugin, StatusEffectPlugin,
+};
 
+mod cooldown;
+mod damage;
+mod damage_numbers;
 mod health;
+mod shield;
+mod status;
 
 pub mod prelude {
+    pub use super::cooldown::*;
+    pub use super::damage::*;
+    pub use super::damage_numbers::*;
     pub use super::health::*;
+    pub use super::shield::*;
+    pub use super::status::*;
 }
 
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((HealthPlugin))
-            .add_systems(Update, read_damage_events)
-            .add_event::<DamageEvent>();
+        app.add_plugins((
+            HealthPlugin,
+            CooldownPlugin,
+            ShieldPlugin,
+            StatusEffectPlugin,
+            DamageNumbersPlugin,
+        ))
+        .add_systems(Update, (read_damage_events, read_heal_events).chain())
+        .add_event::<DamageEvent>()
+        .add_event::<DamageDealtEvent>()
+        .add_event::<HealEvent>()
+        .add_event::<DeathEvent>();
     }
 }
 
@@ -22,13 +46,168 @@ impl Plugin for CombatPlugin {
 pub struct DamageEvent {
     pub amount: f32,
     pub target: Entity,
+    pub kind: DamageKind,
+    /// Whatever dealt the damage, if anything
+    pub source: Option<Entity>,
 }
 
-pub fn read_damage_events(mut evr: EventReader<DamageEvent>, mut hq: Query<&mut Health>) {
+/// Sent after a `DamageEvent` has been resolved against the target's defenses
+#[derive(Event)]
+pub struct DamageDealtEvent {
+    /// How much damage made it past resistances and armor
+    pub amount: f32,
+    pub target: Entity,
+    pub kind: DamageKind,
+    pub source: Option<Entity>,
+    pub crit: bool,
+}
+
+/// Restores health, up to the target's `MaxHealth` if it has one
+#[derive(Event)]
+pub struct HealEvent {
+    pub amount: f32,
+    pub target: Entity,
+    /// Whatever did the healing, if anything
+    pub source: Option<Entity>,
+}
+
+/// Sent exactly once when an entity's health drops to zero
+#[derive(Event)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    /// Whatever dealt the killing blow, if anything
+    pub killer: Option<Entity>,
+    pub damage_kind: DamageKind,
+}
+
+/// Marks an entity whose health has run out and is about to be cleaned up
+#[derive(Component)]
+pub struct Dying;
+
+/// Resolves damage against the target's defenses, in order: invulnerability and hit cooldowns,
+/// critical hits, resistances, armor, shield and finally health
+pub fn read_damage_events(
+    mut evr: EventReader<DamageEvent>,
+    mut hq: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        Option<&Armor>,
+        Option<&mut Shield>,
+        Option<&mut Regeneration>,
+        Option<&InvulnerableAfterHit>,
+        Has<Invulnerable>,
+        Has<Dying>,
+    )>,
+    aq: Query<(Option<&CriticalStrike>, Option<&DamageCooldown>)>,
+    mut cooldowns: ResMut<HitCooldowns>,
+    mut dealt_events: EventWriter<DamageDealtEvent>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut commands: Commands,
+) {
+    let mut rng = thread_rng();
+    // `Invulnerable` only shows up once commands are applied, so remember who got it this frame
+    let mut granted_invulnerability = Vec::new();
     for e in evr.read() {
-        let Ok(mut hp) = hq.get_component_mut::<Health>(e.target) else {
-            return;
+        // The target might have been despawned already, that's fine
+        let Ok((
+            mut hp,
+            resistances,
+            armor,
+            shield,
+            regeneration,
+            invulnerable_after_hit,
+            invulnerable,
+            dying,
+        )) = hq.get_mut(e.target)
+        else {
+            continue;
         };
-        hp.0 -= e.amount;
+
+        if invulnerable || granted_invulnerability.contains(&e.target) {
+            continue;
+        }
+
+        let (crit_strike, damage_cooldown) = match e.source.map(|source| aq.get(source)) {
+            Some(Ok(attacker)) => attacker,
+            _ => (None, None),
+        };
+
+        // Attackers with a cooldown can only hit the same target every so often
+        if let (Some(source), Some(damage_cooldown)) = (e.source, damage_cooldown) {
+            if cooldowns.is_cooling_down(source, e.target, e.kind) {
+                continue;
+            }
+            cooldowns.start(source, e.target, e.kind, damage_cooldown.0);
+        }
+
+        let crit = crit_strike.filter(|c| c.roll(&mut rng));
+
+        let mut amount = e.amount * crit.map_or(1., |c| c.multiplier);
+
+        if let Some(r) = resistances {
+            amount = r.apply(e.kind, amount);
+        }
+
+        if let Some(armor) = armor {
+            amount = armor.reduce(amount);
+        }
+
+        dealt_events.send(DamageDealtEvent {
+            amount,
+            target: e.target,
+            kind: e.kind,
+            source: e.source,
+            crit: crit.is_some(),
+        });
+
+        if let Some(InvulnerableAfterHit(duration)) = invulnerable_after_hit {
+            if amount > 0. {
+                commands
+                    .entity(e.target)
+                    .insert(Invulnerable::new(*duration));
+                granted_invulnerability.push(e.target);
+            }
+        }
+
+        if let Some(mut shield) = shield {
+            amount = shield.absorb(amount);
+        }
+
+        // Getting hurt puts regeneration on hold
+        if let Some(mut r) = regeneration {
+            if amount > 0. {
+                r.delay.reset();
+            }
+        }
+
+        let was_alive = hp.0 > 0.;
+        hp.0 -= amount;
+
+        // Only the hit that takes health across zero counts as the killing blow
+        if was_alive && hp.0 <= 0. && !dying {
+            commands.entity(e.target).insert(Dying);
+            death_events.send(DeathEvent {
+                entity: e.target,
+                killer: e.source,
+                damage_kind: e.kind,
+            });
+        }
+    }
+}
+
+pub fn read_heal_events(
+    mut evr: EventReader<HealEvent>,
+    mut hq: Query<(&mut Health, Option<&MaxHealth>), Without<Dying>>,
+) {
+    for e in evr.read() {
+        let Ok((mut hp, max_hp)) = hq.get_mut(e.target) else {
+            continue;
+        };
+
+        hp.0 += e.amount;
+
+        if let Some(max_hp) = max_hp {
+            hp.0 = hp.0.min(max_hp.0);
+        }
     }
 }
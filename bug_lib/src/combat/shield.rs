@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{read_damage_events, Dying};
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, shields_recharge.after(read_damage_events));
+    }
+}
+
+/// Absorbs damage before it reaches `Health`, and recharges when left alone
+#[derive(Component, Clone)]
+pub struct Shield {
+    pub current: f32,
+    pub max: f32,
+    pub recharge_per_second: f32,
+    /// Restarted whenever the entity takes damage
+    pub recharge_delay: Timer,
+}
+
+impl Shield {
+    pub fn new(max: f32, recharge_per_second: f32, recharge_delay: Duration) -> Self {
+        Shield {
+            current: max,
+            max,
+            recharge_per_second,
+            recharge_delay: Timer::new(recharge_delay, TimerMode::Once),
+        }
+    }
+
+    /// Soaks up as much of the damage as possible, returning whatever gets through
+    pub fn absorb(&mut self, amount: f32) -> f32 {
+        if amount > 0. {
+            self.recharge_delay.reset();
+        }
+
+        let absorbed = amount.min(self.current).max(0.);
+        self.current -= absorbed;
+        amount - absorbed
+    }
+}
+
+/// A `Shield` as written in an enemy definition
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShieldData {
+    pub max: f32,
+    pub recharge_per_second: f32,
+    /// Seconds without taking damage before it starts recharging
+    #[serde(default = "default_recharge_delay")]
+    pub recharge_delay: f32,
+}
+
+fn default_recharge_delay() -> f32 {
+    3.
+}
+
+impl ShieldData {
+    /// Scales with the enemy's health, so tougher enemies get tougher shields
+    pub fn component(&self, health_multiplier: f32) -> Shield {
+        Shield::new(
+            self.max * health_multiplier,
+            self.recharge_per_second * health_multiplier,
+            Duration::from_secs_f32(self.recharge_delay),
+        )
+    }
+}
+
+/// Reduces every hit by a flat amount, then by a percentage of what's left
+#[derive(Component, Default, Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Armor {
+    pub flat: f32,
+    /// From 0 to 1
+    pub percent: f32,
+}
+
+impl Armor {
+    pub fn reduce(&self, amount: f32) -> f32 {
+        (amount - self.flat).max(0.) * (1. - self.percent.clamp(0., 1.))
+    }
+}
+
+fn shields_recharge(mut q: Query<&mut Shield, Without<Dying>>, time: Res<Time>) {
+    for mut s in q.iter_mut() {
+        s.recharge_delay.tick(time.delta());
+
+        if s.recharge_delay.finished() && s.current < s.max {
+            s.current = (s.current + s.recharge_per_second * time.delta_seconds()).min(s.max);
+        }
+    }
+}
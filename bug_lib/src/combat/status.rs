@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::movement::{MovementModifier, SpeedModifier};
+
+use super::{prelude::DamageKind, read_damage_events, DamageEvent};
+
+/// Name of the speed modifier that slows and stuns add up to
+const STATUS_SPEED_MODIFIER: &str = "status";
+
+pub struct StatusEffectPlugin;
+
+impl Plugin for StatusEffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StatusEffectEvent>().add_systems(
+            Update,
+            (apply_status_effect_events, status_effects_tick)
+                .chain()
+                .before(read_damage_events),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum StatusEffectKind {
+    /// Deals poison damage over time
+    Poison,
+    /// Deals fire damage over time
+    Burn,
+    /// Reduces speed by a fraction
+    Slow,
+    /// Stops all movement and re-targeting
+    Stun,
+}
+
+impl StatusEffectKind {
+    /// The kind of damage this effect deals every tick, if any
+    pub fn damage_kind(&self) -> Option<DamageKind> {
+        match self {
+            StatusEffectKind::Poison => Some(DamageKind::Poison),
+            StatusEffectKind::Burn => Some(DamageKind::Fire),
+            StatusEffectKind::Slow | StatusEffectKind::Stun => None,
+        }
+    }
+}
+
+/// What happens when an effect is applied to something that already has an effect of that kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Stacking {
+    /// Restart the duration, keeping the strongest magnitude
+    #[default]
+    Refresh,
+    /// Add a stack (up to `max_stacks`) which multiplies the magnitude, and restart the duration
+    Intensity { max_stacks: u32 },
+    /// Add the new duration on top of whatever time is left
+    Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// Damage per tick for damage over time, fraction of speed removed for slows
+    pub magnitude: f32,
+    pub duration: Timer,
+    /// How often damage over time is dealt
+    pub tick: Timer,
+    pub stacks: u32,
+    pub stacking: Stacking,
+    /// Whatever applied the effect, credited with any damage it deals
+    pub source: Option<Entity>,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusEffectKind, magnitude: f32, duration: Duration) -> Self {
+        StatusEffect {
+            kind,
+            magnitude,
+            duration: Timer::new(duration, TimerMode::Once),
+            tick: Timer::from_seconds(0.5, TimerMode::Repeating),
+            stacks: 1,
+            stacking: Stacking::default(),
+            source: None,
+        }
+    }
+
+    pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+
+    pub fn with_tick_interval(mut self, interval: Duration) -> Self {
+        self.tick = Timer::new(interval, TimerMode::Repeating);
+        self
+    }
+
+    pub fn with_source(mut self, source: Entity) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// The magnitude of the effect with all of its stacks
+    pub fn intensity(&self) -> f32 {
+        self.magnitude * self.stacks as f32
+    }
+}
+
+/// A `StatusEffect` as written in a definition file
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatusEffectData {
+    pub kind: StatusEffectKind,
+    pub magnitude: f32,
+    /// Seconds the effect lasts
+    pub duration: f32,
+    /// Seconds between damage over time ticks
+    #[serde(default = "default_tick_interval")]
+    pub tick_interval: f32,
+    #[serde(default)]
+    pub stacking: Stacking,
+}
+
+fn default_tick_interval() -> f32 {
+    0.5
+}
+
+impl StatusEffectData {
+    pub fn effect(&self, source: Entity) -> StatusEffect {
+        StatusEffect::new(
+            self.kind,
+            self.magnitude,
+            Duration::from_secs_f32(self.duration),
+        )
+        .with_stacking(self.stacking)
+        .with_tick_interval(Duration::from_secs_f32(self.tick_interval))
+        .with_source(source)
+    }
+}
+
+/// All of the timed effects currently affecting an entity
+#[derive(Component, Default, Clone, Debug)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Adds an effect, following its stacking rule if an effect of the same kind is already active
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let Some(existing) = self.0.iter_mut().find(|e| e.kind == effect.kind) else {
+            self.0.push(effect);
+            return;
+        };
+
+        match effect.stacking {
+            Stacking::Refresh => {
+                existing.magnitude = existing.magnitude.max(effect.magnitude);
+                existing.duration = effect.duration;
+            }
+            Stacking::Intensity { max_stacks } => {
+                existing.stacks = (existing.stacks + 1).min(max_stacks.max(1));
+                existing.duration = effect.duration;
+            }
+            Stacking::Duration => {
+                let remaining = existing.duration.remaining();
+                existing.duration =
+                    Timer::new(remaining + effect.duration.duration(), TimerMode::Once);
+            }
+        }
+
+        existing.stacking = effect.stacking;
+        if effect.source.is_some() {
+            existing.source = effect.source;
+        }
+    }
+
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.0.iter().any(|e| e.kind == kind)
+    }
+
+    /// How much the active slows and stuns scale speed by
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.has(StatusEffectKind::Stun) {
+            return 0.;
+        }
+
+        self.0
+            .iter()
+            .filter(|e| e.kind == StatusEffectKind::Slow)
+            .map(|e| (1. - e.intensity()).clamp(0., 1.))
+            .product()
+    }
+
+    /// Whether the entity is too dazed to pick a new target
+    pub fn suppresses_targeting(&self) -> bool {
+        self.has(StatusEffectKind::Stun)
+    }
+}
+
+/// Applies a status effect to the target
+#[derive(Event)]
+pub struct StatusEffectEvent {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+fn apply_status_effect_events(
+    mut evr: EventReader<StatusEffectEvent>,
+    mut q: Query<&mut StatusEffects>,
+    mut commands: Commands,
+    mut new: Local<HashMap<Entity, StatusEffects>>,
+) {
+    for e in evr.read() {
+        if let Ok(mut effects) = q.get_mut(e.target) {
+            effects.apply(e.effect.clone());
+        } else {
+            // Gather everything applied this frame first, one insert would overwrite another
+            new.entry(e.target).or_default().apply(e.effect.clone());
+        }
+    }
+
+    for (target, effects) in new.drain() {
+        if let Some(mut entity) = commands.get_entity(target) {
+            entity.try_insert(effects);
+        }
+    }
+}
+
+fn status_effects_tick(
+    mut q: Query<(Entity, &mut StatusEffects, Option<&mut MovementModifier>)>,
+    time: Res<Time>,
+    mut dewr: EventWriter<DamageEvent>,
+) {
+    for (e, mut effects, modifier) in q.iter_mut() {
+        for effect in effects.0.iter_mut() {
+            effect.duration.tick(time.delta());
+
+            let Some(kind) = effect.kind.damage_kind() else {
+                continue;
+            };
+
+            effect.tick.tick(time.delta());
+            for _ in 0..effect.tick.times_finished_this_tick() {
+                dewr.send(DamageEvent {
+                    amount: effect.intensity(),
+                    target: e,
+                    kind,
+                    source: effect.source,
+                });
+            }
+        }
+
+        effects.0.retain(|effect| !effect.duration.finished());
+
+        if let Some(mut modifier) = modifier {
+            let multiplier = effects.speed_multiplier();
+            if multiplier < 1. {
+                modifier.insert(SpeedModifier::multiply(STATUS_SPEED_MODIFIER, multiplier));
+            } else if modifier.get(STATUS_SPEED_MODIFIER).is_some() {
+                modifier.remove(STATUS_SPEED_MODIFIER);
+            }
+        }
+    }
+}
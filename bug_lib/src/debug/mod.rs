@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision::visualize_colliders,
+    enemy::{prelude::RangedAttack, Enemy, EnemySpawnConfig},
+    flow_field::FlowField,
+    movement::{EffectiveSpeed, Momentum, Speed, Velocity},
+    steering::Steering,
+    swatter::swatter_follows_mouse,
+};
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlayConfig>()
+            .add_systems(Update, toggle_debug_overlay)
+            .add_systems(
+                Update,
+                (
+                    visualize_colliders.run_if(|c: Res<DebugOverlayConfig>| c.colliders),
+                    visualize_velocities.run_if(|c: Res<DebugOverlayConfig>| c.velocities),
+                    visualize_targets.run_if(|c: Res<DebugOverlayConfig>| c.targets),
+                    visualize_spawn_radius.run_if(|c: Res<DebugOverlayConfig>| c.spawn_radius),
+                    visualize_attack_ranges.run_if(|c: Res<DebugOverlayConfig>| c.attack_ranges),
+                    visualize_flow_field.run_if(|c: Res<DebugOverlayConfig>| c.flow_field),
+                )
+                    .after(toggle_debug_overlay)
+                    .after(swatter_follows_mouse)
+                    .distributive_run_if(|c: Res<DebugOverlayConfig>| c.enabled),
+            );
+    }
+}
+
+/// What the debug overlay shows, and the key that turns it on and off
+#[derive(Resource)]
+pub struct DebugOverlayConfig {
+    pub enabled: bool,
+    pub toggle_key: KeyCode,
+    pub colliders: bool,
+    pub velocities: bool,
+    /// Lines from each enemy to whatever it's chasing
+    pub targets: bool,
+    pub spawn_radius: bool,
+    /// How close ranged enemies get before they stop to shoot
+    pub attack_ranges: bool,
+    /// Off by default, it's a lot of arrows
+    pub flow_field: bool,
+}
+
+impl Default for DebugOverlayConfig {
+    fn default() -> Self {
+        DebugOverlayConfig {
+            // Release builds keep it hidden until someone asks for it
+            enabled: cfg!(debug_assertions),
+            toggle_key: KeyCode::F3,
+            colliders: true,
+            velocities: true,
+            targets: true,
+            spawn_radius: true,
+            attack_ranges: true,
+            flow_field: false,
+        }
+    }
+}
+
+fn toggle_debug_overlay(mut config: ResMut<DebugOverlayConfig>, keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(config.toggle_key) {
+        config.enabled = !config.enabled;
+    }
+}
+
+/// Arrows showing where things are heading and how fast
+fn visualize_velocities(
+    q: Query<(
+        &Transform,
+        &Velocity,
+        &Speed,
+        Option<&EffectiveSpeed>,
+        Option<&Momentum>,
+    )>,
+    mut gizmos: Gizmos,
+) {
+    for (t, v, s, es, momentum) in q.iter() {
+        let start = t.translation.xy();
+        let velocity = match momentum {
+            Some(momentum) => momentum.0,
+            None => v.0 * es.map_or(s.0, |es| es.0),
+        };
+        let end = start + velocity;
+        let Some(direction) = velocity.try_normalize() else {
+            continue;
+        };
+
+        gizmos.line_2d(start, end, Color::YELLOW);
+        for side in [-1., 1.] {
+            let head = Vec2::from_angle(side * 2.6).rotate(direction) * 8.;
+            gizmos.line_2d(end, end + head, Color::YELLOW);
+        }
+    }
+}
+
+/// Lines from each enemy to whatever it's steering towards, unless its steering is paused
+fn visualize_targets(
+    eq: Query<(&Transform, &Steering), With<Enemy>>,
+    tq: Query<&Transform>,
+    mut gizmos: Gizmos,
+) {
+    for (t, steering) in eq.iter() {
+        if steering.paused {
+            continue;
+        }
+        let Some(Ok(target)) = steering.target.map(|target| tq.get(target)) else {
+            continue;
+        };
+
+        gizmos.line_2d(
+            t.translation.xy(),
+            target.translation.xy(),
+            Color::RED.with_a(0.25),
+        );
+    }
+}
+
+/// The ellipse enemies spawn on
+fn visualize_spawn_radius(config: Res<EnemySpawnConfig>, mut gizmos: Gizmos) {
+    let points = (0..=64).map(|i| {
+        let angle = i as f32 / 64. * std::f32::consts::TAU;
+        Vec2::new(angle.cos(), angle.sin()) * config.spawn_radius
+    });
+    gizmos.linestrip_2d(points, Color::PURPLE);
+}
+
+fn visualize_attack_ranges(q: Query<(&Transform, &RangedAttack)>, mut gizmos: Gizmos) {
+    for (t, ranged) in q.iter() {
+        gizmos
+            .circle_2d(t.translation.xy(), ranged.range, Color::GREEN.with_a(0.25))
+            .segments(64);
+    }
+}
+
+/// Which way each cell of the flow field points, with blocked cells crossed out
+fn visualize_flow_field(field: Res<FlowField>, mut gizmos: Gizmos) {
+    let half = field.cell_size / 2.;
+    for (center, direction, blocked) in field.iter() {
+        if blocked {
+            gizmos.line_2d(
+                center - Vec2::splat(half),
+                center + Vec2::splat(half),
+                Color::GRAY,
+            );
+            gizmos.line_2d(
+                center + Vec2::new(-half, half),
+                center + Vec2::new(half, -half),
+                Color::GRAY,
+            );
+        } else if let Some(direction) = direction {
+            gizmos.line_2d(
+                center - direction * half * 0.6,
+                center + direction * half * 0.6,
+                Color::TEAL.with_a(0.4),
+            );
+        }
+    }
+}
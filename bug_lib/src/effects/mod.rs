@@ -0,0 +1,240 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::combat::Dying;
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HitEffectEvent>()
+            .add_event::<HitStopEvent>()
+            .init_resource::<HitStop>()
+            .add_systems(
+                Update,
+                (
+                    (apply_hit_effect_events, hit_effects_tick).chain(),
+                    hit_stop_tick,
+                ),
+            );
+    }
+}
+
+/// Flashes toward plain white
+pub const FLASH_WHITE: Color = Color::WHITE;
+
+#[derive(Debug, Clone, Copy)]
+pub enum HitEffectKind {
+    /// Tints toward a color, then fades back to the original
+    Flash(Color),
+    /// Scales up by a fraction, then eases back
+    ScalePunch(f32),
+    /// Stretches vertically and squashes horizontally by a fraction, then eases back
+    SquashStretch(f32),
+}
+
+#[derive(Debug, Clone)]
+pub struct HitEffect {
+    pub kind: HitEffectKind,
+    pub timer: Timer,
+}
+
+impl HitEffect {
+    pub fn new(kind: HitEffectKind, duration: Duration) -> Self {
+        HitEffect {
+            kind,
+            timer: Timer::new(duration, TimerMode::Once),
+        }
+    }
+
+    pub fn flash(color: Color, duration: Duration) -> Self {
+        HitEffect::new(HitEffectKind::Flash(color), duration)
+    }
+
+    pub fn scale_punch(amount: f32, duration: Duration) -> Self {
+        HitEffect::new(HitEffectKind::ScalePunch(amount), duration)
+    }
+
+    pub fn squash_stretch(amount: f32, duration: Duration) -> Self {
+        HitEffect::new(HitEffectKind::SquashStretch(amount), duration)
+    }
+
+    /// How strongly the effect still applies, from 1 when it starts to 0 when it's done
+    fn strength(&self) -> f32 {
+        1. - self.timer.percent()
+    }
+}
+
+/// The effects currently playing on an entity, and the color and scale to go back to afterwards
+#[derive(Component, Default)]
+pub struct HitEffects {
+    effects: Vec<HitEffect>,
+    base_color: Option<Color>,
+    base_scale: Option<Vec3>,
+}
+
+/// Plays an effect on a `Sprite` or `UiImage`, on top of any effects already playing
+#[derive(Event)]
+pub struct HitEffectEvent {
+    pub target: Entity,
+    pub effect: HitEffect,
+}
+
+/// Freezes virtual time for a moment to make a hit land harder
+#[derive(Event)]
+pub struct HitStopEvent(pub Duration);
+
+#[derive(Resource, Default)]
+pub struct HitStop(pub Option<Timer>);
+
+fn apply_hit_effect_events(
+    mut evr: EventReader<HitEffectEvent>,
+    mut q: Query<
+        (
+            Option<&mut HitEffects>,
+            Option<&Sprite>,
+            Option<&BackgroundColor>,
+            Option<&Transform>,
+        ),
+        Without<Dying>,
+    >,
+    mut pending: Local<Vec<(Entity, HitEffects)>>,
+    mut commands: Commands,
+) {
+    for e in evr.read() {
+        let Ok((effects, sprite, background, transform)) = q.get_mut(e.target) else {
+            continue;
+        };
+
+        if let Some(mut effects) = effects {
+            effects.effects.push(e.effect.clone());
+            continue;
+        }
+
+        // Entities getting their first effect this frame are collected so multiple effects
+        // sent at once all end up on the same component
+        if let Some((_, effects)) = pending.iter_mut().find(|(p, _)| *p == e.target) {
+            effects.effects.push(e.effect.clone());
+            continue;
+        }
+
+        pending.push((
+            e.target,
+            HitEffects {
+                effects: vec![e.effect.clone()],
+                base_color: sprite.map(|s| s.color).or(background.map(|b| b.0)),
+                base_scale: transform.map(|t| t.scale),
+            },
+        ));
+    }
+
+    // The target can still be despawned before this gets applied, e.g. by the hit that
+    // killed it
+    for (e, effects) in pending.drain(..) {
+        if let Some(mut entity) = commands.get_entity(e) {
+            entity.try_insert(effects);
+        }
+    }
+}
+
+fn hit_effects_tick(
+    mut q: Query<(
+        Entity,
+        &mut HitEffects,
+        Option<&mut Sprite>,
+        Option<&mut BackgroundColor>,
+        Option<&mut Transform>,
+    )>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (e, mut effects, sprite, background, transform) in q.iter_mut() {
+        for effect in effects.effects.iter_mut() {
+            effect.timer.tick(time.delta());
+        }
+        effects.effects.retain(|effect| !effect.timer.finished());
+
+        // The most recent flash wins, fading from its color back to the original one
+        let color = effects
+            .effects
+            .iter()
+            .rev()
+            .find_map(|effect| match effect.kind {
+                HitEffectKind::Flash(color) => Some((color, effect.strength())),
+                _ => None,
+            })
+            .map(|(color, strength)| match effects.base_color {
+                Some(base) => mix(base, color, strength),
+                None => color,
+            })
+            .or(effects.base_color);
+
+        let mut scale = Vec3::ONE;
+        for effect in effects.effects.iter() {
+            let strength = effect.strength();
+            match effect.kind {
+                HitEffectKind::ScalePunch(amount) => {
+                    scale *= 1. + amount * strength;
+                }
+                HitEffectKind::SquashStretch(amount) => {
+                    scale.x *= 1. - amount * strength;
+                    scale.y *= 1. + amount * strength;
+                }
+                HitEffectKind::Flash(_) => {}
+            }
+        }
+
+        if let Some(color) = color {
+            if let Some(mut sprite) = sprite {
+                sprite.color = color;
+            } else if let Some(mut background) = background {
+                background.0 = color;
+            }
+        }
+
+        if let (Some(base_scale), Some(mut transform)) = (effects.base_scale, transform) {
+            transform.scale = base_scale * scale;
+        }
+
+        if effects.effects.is_empty() {
+            commands.entity(e).remove::<HitEffects>();
+        }
+    }
+}
+
+/// Blends from `a` to `b`, `t` of the way
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let a = Vec4::from(a.as_rgba_f32());
+    let b = Vec4::from(b.as_rgba_f32());
+    Color::from(a.lerp(b, t.clamp(0., 1.)))
+}
+
+fn hit_stop_tick(
+    mut evr: EventReader<HitStopEvent>,
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    for e in evr.read() {
+        // Overlapping hit stops don't add up, the longest one wins
+        let remaining = hit_stop
+            .0
+            .as_ref()
+            .map_or(Duration::ZERO, |t| t.remaining());
+        if e.0 > remaining {
+            hit_stop.0 = Some(Timer::new(e.0, TimerMode::Once));
+            virtual_time.pause();
+        }
+    }
+
+    let Some(timer) = hit_stop.0.as_mut() else {
+        return;
+    };
+
+    timer.tick(real_time.delta());
+    if timer.finished() {
+        hit_stop.0 = None;
+        virtual_time.unpause();
+    }
+}
@@ -0,0 +1,495 @@
+//! Enemy types described in `.enemy.ron` files, so new bugs can be added without touching code
+//!
+//! ```ron
+//! (
+//!     name: "ant",
+//!     sprite: "../sprites/enemy1.png",
+//!     health: (50.0, 100.0),
+//!     speed: (50.0, 75.0),
+//!     damage: (5.0, 10.0),
+//!     collider_radius: 32.0,
+//!     required_difficulty: 0,
+//!     xp_drop: 1,
+//!     behavior: (wander: 0.6, separation: 0.5),
+//! )
+//! ```
+//!
+//! The sprite path is relative to the definition file. `mass`, `armor`, `resistances`,
+//! `required_difficulty`, `xp_drop`, `behavior`, `ranged`, `split` and `shield` can be left out.
+//! Enemies that shoot from a distance instead of walking into the tower add something like
+//!
+//! ```ron
+//! ranged: Some((
+//!     range: 300.0,
+//!     windup: 0.8,
+//!     cooldown: 2.0,
+//!     damage: 8.0,
+//!     projectile_speed: 250.0,
+//!     // Optional, lingers on whatever gets hit
+//!     status: Some((kind: Poison, magnitude: 1.0, duration: 3.0)),
+//! )),
+//! ```
+//!
+//! and ones that break apart when they die add `split: Some((into: "ant", count: 3))`. Each
+//! split off child is smaller, faster, weaker and drops fewer bug cores than its parent.
+//! Enemies with `shield: Some((max: 40.0, recharge_per_second: 15.0))` soak up that much damage
+//! before it reaches their health, and recharge it after `recharge_delay` seconds unharmed.
+
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, ParseAssetPathError},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    combat::prelude::{Armor, DamageKind, Resistances, ShieldData},
+    movement::Mass,
+    steering::SteeringWeights,
+};
+
+use super::{
+    prelude::{RangedAttackData, SplitData},
+    EnemyInitData,
+};
+
+/// A named enemy type, loaded from a `.enemy.ron` file
+#[derive(Asset, TypePath, Clone)]
+pub struct EnemyDefinition {
+    /// What the enemy is called in the `EnemyList`
+    pub name: String,
+    pub data: EnemyInitData,
+}
+
+/// What's actually written in a `.enemy.ron` file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnemyDefinitionFile {
+    name: String,
+    sprite: String,
+    /// Min and max, each enemy rolls something in between
+    health: (f32, f32),
+    speed: (f32, f32),
+    damage: (f32, f32),
+    collider_radius: f32,
+    #[serde(default)]
+    required_difficulty: i32,
+    #[serde(default = "default_xp_drop")]
+    xp_drop: u32,
+    #[serde(default = "default_mass")]
+    mass: f32,
+    #[serde(default)]
+    armor: Armor,
+    #[serde(default)]
+    resistances: HashMap<DamageKind, f32>,
+    #[serde(default)]
+    behavior: SteeringWeights,
+    #[serde(default)]
+    ranged: Option<RangedAttackData>,
+    #[serde(default)]
+    split: Option<SplitData>,
+    #[serde(default)]
+    shield: Option<ShieldData>,
+}
+
+fn default_xp_drop() -> u32 {
+    1
+}
+
+fn default_mass() -> f32 {
+    1.
+}
+
+#[derive(Debug, Error)]
+pub enum EnemyDefinitionError {
+    #[error("could not read enemy definition: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse enemy definition: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("enemy definition has no name")]
+    MissingName,
+    #[error("`{enemy}` has an invalid sprite path `{path}`: {source}")]
+    InvalidSprite {
+        enemy: String,
+        path: String,
+        source: ParseAssetPathError,
+    },
+    #[error("`{enemy}` has an invalid {field} range ({min}, {max}), it needs 0 <= min < max")]
+    InvalidRange {
+        enemy: String,
+        field: &'static str,
+        min: f32,
+        max: f32,
+    },
+    #[error("`{enemy}` splits, but doesn't say what into")]
+    MissingSplitTarget { enemy: String },
+    #[error("`{enemy}` needs a {field} above zero, not {value}")]
+    NotPositive {
+        enemy: String,
+        field: &'static str,
+        value: f32,
+    },
+    #[error("`{enemy}` can't have a negative {field}, got {value}")]
+    Negative {
+        enemy: String,
+        field: &'static str,
+        value: f32,
+    },
+}
+
+impl EnemyDefinitionFile {
+    /// Catches anything that would panic or misbehave once the enemy spawns
+    fn validate(&self) -> Result<(), EnemyDefinitionError> {
+        if self.name.trim().is_empty() {
+            return Err(EnemyDefinitionError::MissingName);
+        }
+
+        for (field, (min, max)) in [
+            ("health", self.health),
+            ("speed", self.speed),
+            ("damage", self.damage),
+        ] {
+            if !(min >= 0. && min < max && max.is_finite()) {
+                return Err(EnemyDefinitionError::InvalidRange {
+                    enemy: self.name.clone(),
+                    field,
+                    min,
+                    max,
+                });
+            }
+        }
+
+        let mut non_negative = Vec::new();
+        let mut positive = vec![
+            ("minimum health", self.health.0),
+            ("collider_radius", self.collider_radius),
+            ("mass", self.mass),
+        ];
+        if let Some(ranged) = &self.ranged {
+            positive.extend([
+                ("ranged range", ranged.range),
+                ("ranged cooldown", ranged.cooldown),
+                ("ranged projectile_speed", ranged.projectile_speed),
+                ("ranged projectile_radius", ranged.projectile_radius),
+                ("ranged amount", ranged.amount as f32),
+            ]);
+            non_negative.extend([
+                ("ranged windup", ranged.windup),
+                ("ranged damage", ranged.damage),
+            ]);
+            if let Some(status) = &ranged.status {
+                positive.push(("ranged status tick_interval", status.tick_interval));
+                non_negative.extend([
+                    ("ranged status magnitude", status.magnitude),
+                    ("ranged status duration", status.duration),
+                ]);
+            }
+        }
+        if let Some(split) = &self.split {
+            if split.into.trim().is_empty() {
+                return Err(EnemyDefinitionError::MissingSplitTarget {
+                    enemy: self.name.clone(),
+                });
+            }
+
+            positive.extend([
+                ("split count", split.count as f32),
+                ("split max_generations", split.max_generations as f32),
+                ("split shrink", split.shrink),
+                ("split speedup", split.speedup),
+                ("split weaken", split.weaken),
+            ]);
+            non_negative.extend([
+                ("split scatter", split.scatter),
+                ("split experience_share", split.experience_share),
+            ]);
+        }
+
+        if let Some(shield) = &self.shield {
+            positive.push(("shield max", shield.max));
+            non_negative.extend([
+                ("shield recharge_per_second", shield.recharge_per_second),
+                ("shield recharge_delay", shield.recharge_delay),
+            ]);
+        }
+
+        for (field, value) in positive {
+            if !(value > 0. && value.is_finite()) {
+                return Err(EnemyDefinitionError::NotPositive {
+                    enemy: self.name.clone(),
+                    field,
+                    value,
+                });
+            }
+        }
+
+        for (field, value) in non_negative {
+            if !(value >= 0. && value.is_finite()) {
+                return Err(EnemyDefinitionError::Negative {
+                    enemy: self.name.clone(),
+                    field,
+                    value,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct EnemyDefinitionLoader;
+
+impl AssetLoader for EnemyDefinitionLoader {
+    type Asset = EnemyDefinition;
+    type Settings = ();
+    type Error = EnemyDefinitionError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: EnemyDefinitionFile = ron::de::from_bytes(&bytes)?;
+            file.validate()?;
+
+            let sprite_path = load_context
+                .asset_path()
+                .resolve_embed(&file.sprite)
+                .map_err(|source| EnemyDefinitionError::InvalidSprite {
+                    enemy: file.name.clone(),
+                    path: file.sprite.clone(),
+                    source,
+                })?;
+
+            Ok(EnemyDefinition {
+                data: EnemyInitData {
+                    sprite: load_context.load(sprite_path),
+                    health_range: file.health.0..file.health.1,
+                    speed_range: file.speed.0..file.speed.1,
+                    damage_range: file.damage.0..file.damage.1,
+                    collider_radius: file.collider_radius,
+                    resistances: Resistances(file.resistances.into_iter().collect()),
+                    armor: file.armor,
+                    mass: Mass(file.mass),
+                    steering: file.behavior,
+                    required_difficulty: file.required_difficulty,
+                    xp_drop: file.xp_drop,
+                    ranged: file.ranged,
+                    split: file.split,
+                    shield: file.shield,
+                },
+                name: file.name,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemy.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANT: &str = r#"(
+        name: "ant",
+        sprite: "ant.png",
+        health: (50.0, 100.0),
+        speed: (50.0, 75.0),
+        damage: (5.0, 10.0),
+        collider_radius: 32.0,
+    )"#;
+
+    const RANGED: &str = "ranged: Some((range: 300.0, windup: 0.8, cooldown: 2.0, damage: 8.0, \
+        projectile_speed: 250.0)),";
+
+    /// Stands in for the projectile speed in `RANGED`
+    const STATUS: &str = "250.0, status: Some((kind: Poison, magnitude: 1.0, duration: 3.0, \
+        tick_interval: 0.5, stacking: Intensity(max_stacks: 3)))";
+
+    fn parse(source: &str) -> Result<(), EnemyDefinitionError> {
+        let file: EnemyDefinitionFile = ron::from_str(source)?;
+        file.validate()
+    }
+
+    /// Parses an ant with one bit of it swapped out
+    fn ant_with(from: &str, to: &str) -> Result<(), EnemyDefinitionError> {
+        parse(&ANT.replace(from, to))
+    }
+
+    #[test]
+    fn accepts_a_plain_enemy() {
+        assert!(parse(ANT).is_ok());
+        assert!(ant_with(
+            "collider_radius: 32.0,",
+            r#"collider_radius: 32.0, split: Some((into: "ant", count: 2)),"#
+        )
+        .is_ok());
+        assert!(ant_with(
+            "collider_radius: 32.0,",
+            &format!("collider_radius: 32.0, {RANGED}")
+        )
+        .is_ok());
+        assert!(ant_with(
+            "collider_radius: 32.0,",
+            &format!("collider_radius: 32.0, {}", RANGED.replace("250.0", STATUS))
+        )
+        .is_ok());
+        assert!(ant_with(
+            "collider_radius: 32.0,",
+            "collider_radius: 32.0, shield: Some((max: 40.0, recharge_per_second: 10.0)),"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        for (from, to, expected) in [
+            ("speed: (50.0, 75.0)", "speed: (75.0, 50.0)", "speed"),
+            ("damage: (5.0, 10.0)", "damage: (5.0, 5.0)", "damage"),
+            ("health: (50.0, 100.0)", "health: (-1.0, 100.0)", "health"),
+        ] {
+            match ant_with(from, to) {
+                Err(EnemyDefinitionError::InvalidRange { field, .. }) => {
+                    assert_eq!(field, expected)
+                }
+                other => panic!("{to} should be an invalid range, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_values_that_need_to_be_positive() {
+        for (from, to, expected) in [
+            (
+                "health: (50.0, 100.0)",
+                "health: (0.0, 100.0)",
+                "minimum health",
+            ),
+            (
+                "collider_radius: 32.0",
+                "collider_radius: 0.0",
+                "collider_radius",
+            ),
+            (
+                "collider_radius: 32.0,",
+                "collider_radius: 32.0, mass: -1.0,",
+                "mass",
+            ),
+            (
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0, split: Some((into: "ant", count: 0)),"#,
+                "split count",
+            ),
+            (
+                "collider_radius: 32.0,",
+                &format!(
+                    "collider_radius: 32.0, {}",
+                    RANGED.replace("windup: 0.8", "windup: -0.5")
+                ),
+                "ranged windup",
+            ),
+            (
+                "collider_radius: 32.0,",
+                &format!(
+                    "collider_radius: 32.0, {}",
+                    RANGED.replace("damage: 8.0", "damage: -8.0")
+                ),
+                "ranged damage",
+            ),
+            (
+                "collider_radius: 32.0,",
+                &format!(
+                    "collider_radius: 32.0, {}",
+                    RANGED.replace("250.0", &STATUS.replace("3.0", "-3.0"))
+                ),
+                "ranged status duration",
+            ),
+            (
+                "collider_radius: 32.0,",
+                &format!(
+                    "collider_radius: 32.0, {}",
+                    RANGED.replace("250.0", &STATUS.replace("0.5", "0.0"))
+                ),
+                "ranged status tick_interval",
+            ),
+            (
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0, split: Some((into: "ant", count: 2, weaken: 0.0)),"#,
+                "split weaken",
+            ),
+            (
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0, split: Some((into: "ant", count: 2, weaken: NaN)),"#,
+                "split weaken",
+            ),
+            (
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0, split: Some((into: "ant", count: 2, scatter: -1.0)),"#,
+                "split scatter",
+            ),
+            (
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0,
+                    split: Some((into: "ant", count: 2, experience_share: -0.5)),"#,
+                "split experience_share",
+            ),
+            (
+                "collider_radius: 32.0,",
+                "collider_radius: 32.0, shield: Some((max: 0.0, recharge_per_second: 10.0)),",
+                "shield max",
+            ),
+            (
+                "collider_radius: 32.0,",
+                "collider_radius: 32.0, shield: Some((max: 40.0, recharge_per_second: -1.0)),",
+                "shield recharge_per_second",
+            ),
+            (
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0,
+                    shield: Some((max: 40.0, recharge_per_second: 10.0, recharge_delay: -3.0)),"#,
+                "shield recharge_delay",
+            ),
+        ] {
+            match ant_with(from, to) {
+                Err(
+                    EnemyDefinitionError::NotPositive { field, .. }
+                    | EnemyDefinitionError::Negative { field, .. },
+                ) => assert_eq!(field, expected),
+                other => panic!("{to} should be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_missing_names() {
+        assert!(matches!(
+            ant_with(r#"name: "ant""#, r#"name: " ""#),
+            Err(EnemyDefinitionError::MissingName)
+        ));
+        assert!(matches!(
+            ant_with(
+                "collider_radius: 32.0,",
+                r#"collider_radius: 32.0, split: Some((into: "", count: 2)),"#
+            ),
+            Err(EnemyDefinitionError::MissingSplitTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(matches!(
+            ant_with("collider_radius", "colider_radius"),
+            Err(EnemyDefinitionError::Ron(_))
+        ));
+    }
+}
@@ -1,19 +1,50 @@
-use std::{f32::consts::PI, ops::Range, time::Duration};
+use std::{ops::Range, time::Duration};
 
 use bevy::{prelude::*, render::render_resource::Texture, utils::HashMap};
 
 use crate::{
     asset_loading::AppAssets,
-    collision::Collider,
-    combat::{prelude::Health, read_damage_events},
+    collision::{
+        prelude::{Colliding, CollisionLayers, Layer, Separation},
+        Collider,
+    },
+    combat::{
+        prelude::{
+            Armor, DamageCooldown, DamageKind, Health, MaxHealth, Resistances, ShieldData,
+            StatusEffects,
+        },
+        read_damage_events, DamageEvent, DeathEvent,
+    },
     game::DifficultyConfig,
-    movement::{self, velocity_moves_transforms, MovementBundle, Speed, Velocity},
+    movement::{
+        self, velocity_moves_transforms, Acceleration, Drag, Mass, Momentum, MovementBundle,
+        MovementModifier, Speed, SpeedModifier, Velocity,
+    },
+    projectile::projectile_emitters_emit,
     state::AppState,
+    steering::{Steering, SteeringWeights},
     tower::Tower,
-    xp::ExperienceBundle,
+    xp::{ExperienceBundle, EXPERIENCE_LAYERS},
 };
 use rand::{distributions::uniform::SampleRange, prelude::*};
 
+use self::prelude::{
+    enemies_split, ranged_enemies_keep_their_distance, EnemyDefinition, EnemyDefinitionLoader,
+    Generation, RangedAttackData, SpawnPattern, SplitData, Splits,
+};
+
+mod definition;
+mod pattern;
+mod ranged;
+mod split;
+
+pub mod prelude {
+    pub use super::definition::*;
+    pub use super::pattern::*;
+    pub use super::ranged::*;
+    pub use super::split::*;
+}
+
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
@@ -21,13 +52,8 @@ impl Plugin for EnemyPlugin {
         app.insert_resource(EnemySpawnConfig {
             timer: Timer::from_seconds(5., TimerMode::Repeating),
             spawn_radius: Vec2::new(1920. / 1.5, 1080. / 1.5),
+            pattern: SpawnPattern::default(),
         })
-        .add_systems(
-            Update,
-            ((enemies_spawn,)
-                .chain()
-                .distributive_run_if(in_state(AppState::InGame))),
-        )
         .add_systems(
             Update,
             enemies_hate_the_tower
@@ -36,15 +62,34 @@ impl Plugin for EnemyPlugin {
         )
         .add_systems(
             Update,
-            (enemies_damage_the_tower, flip_enemy_sprite_with_velocity)
+            (
+                enemies_damage_the_tower.before(read_damage_events),
+                flip_enemy_sprite_with_velocity,
+            )
                 .distributive_run_if(in_state(AppState::InGame)),
         )
         .add_systems(
             Update,
-            enemies_die
+            (enemies_split, enemies_die)
+                .chain()
                 .after(read_damage_events)
+                .distributive_run_if(in_state(AppState::InGame)),
+        )
+        .add_event::<EnemyKilled>()
+        .init_asset::<EnemyDefinition>()
+        .init_asset_loader::<EnemyDefinitionLoader>()
+        .init_resource::<EnemyList>()
+        .add_systems(Update, enemy_definitions_fill_enemy_list)
+        .add_systems(
+            Update,
+            ranged_enemies_keep_their_distance
+                .after(enemies_hate_the_tower)
+                .before(projectile_emitters_emit)
                 .run_if(in_state(AppState::InGame)),
         );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Startup, load_extra_enemy_definitions);
     }
 }
 
@@ -54,9 +99,24 @@ pub struct Enemy;
 #[derive(Bundle, Default)]
 pub struct EnemyBundle {
     health: Health,
+    max_health: MaxHealth,
+    resistances: Resistances,
+    armor: Armor,
+    status_effects: StatusEffects,
+    contact_damage: ContactDamage,
+    experience_drop: ExperienceDrop,
+    generation: Generation,
+    damage_cooldown: DamageCooldown,
     movement_bundle: MovementBundle,
     sprite_bundle: SpriteBundle,
     collider: Collider,
+    layers: CollisionLayers,
+    mass: Mass,
+    acceleration: Acceleration,
+    drag: Drag,
+    momentum: Momentum,
+    separation: Separation,
+    steering: Steering,
     movement_cooldown: MovementCooldown,
     marker: Enemy,
 }
@@ -66,115 +126,246 @@ pub struct EnemyInitData {
     pub sprite: Handle<Image>,
     pub health_range: Range<f32>,
     pub speed_range: Range<f32>,
-    //TODO
+    /// Damage dealt to the tower on contact, once per `CONTACT_DAMAGE_COOLDOWN`
     pub damage_range: Range<f32>,
-    pub movement_cooldown_range: Range<f32>,
+    pub collider_radius: f32,
+    pub resistances: Resistances,
+    pub armor: Armor,
+    /// Heavier enemies shove lighter ones out of the way
+    pub mass: Mass,
+    /// How this enemy moves around on its way to the tower
+    pub steering: SteeringWeights,
     // The required difficulty for this enemy to spawn
     pub required_difficulty: i32,
+    /// How many bug cores it leaves behind
+    pub xp_drop: u32,
+    /// Enemies with a ranged attack stop short of the tower and shoot it
+    pub ranged: Option<RangedAttackData>,
+    /// What the enemy breaks into when it dies
+    pub split: Option<SplitData>,
+    pub shield: Option<ShieldData>,
 }
 
-#[derive(Resource)]
+/// Every known enemy type by name, filled in from `EnemyDefinition` assets
+#[derive(Resource, Default)]
 pub struct EnemyList(pub HashMap<String, EnemyInitData>);
 
+impl EnemyList {
+    /// All of the enemies, easiest first
+    pub fn by_difficulty(&self) -> Vec<EnemyInitData> {
+        let mut enemies: Vec<_> = self.0.iter().collect();
+        enemies.sort_by(|(a_name, a), (b_name, b)| {
+            a.required_difficulty
+                .cmp(&b.required_difficulty)
+                .then_with(|| a_name.cmp(b_name))
+        });
+        enemies.into_iter().map(|(_, e)| e.clone()).collect()
+    }
+}
+
 #[derive(Resource)]
 pub struct EnemyPool(pub Vec<EnemyInitData>);
 
 #[derive(Resource)]
 pub struct EnemySpawnConfig {
+    /// How often the endless fallback spawns a batch
     pub timer: Timer,
     pub spawn_radius: Vec2,
+    /// Where the endless fallback places each batch
+    pub pattern: SpawnPattern,
 }
 
+/// How hard enemies push themselves along, divided by their mass to get their acceleration
+const ENEMY_THRUST: f32 = 300.;
+const ENEMY_DRAG: f32 = 4.;
+
 #[derive(Component, Default)]
 pub struct MovementCooldown(pub Timer);
 
-fn enemies_spawn(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut config: ResMut<EnemySpawnConfig>,
-    difficulty_config: Res<DifficultyConfig>,
-    enemy_data_pool: Res<EnemyPool>,
+pub const ENEMY_LAYERS: CollisionLayers = CollisionLayers::new(
+    Layer::ENEMY,
+    Layer::ENEMY | Layer::TOWER | Layer::SWATTER | Layer::PROJECTILE | Layer::OBSTACLE,
+);
+
+/// How many bug cores an enemy drops when it dies
+#[derive(Component, Default)]
+pub struct ExperienceDrop(pub u32);
+
+/// Where extra `.enemy.ron` files can be dropped in, inside the game's assets folder
+#[cfg(not(target_arch = "wasm32"))]
+const EXTRA_ENEMIES_FOLDER: &str = "enemies";
+
+/// Keeps any extra enemy definitions loaded from disk alive
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Resource)]
+struct ExtraEnemyDefinitions(Handle<bevy::asset::LoadedFolder>);
+
+/// Loads enemy definitions from the assets folder next to the game, on top of the built in
+/// ones, so new bugs can be added without a rebuild
+#[cfg(not(target_arch = "wasm32"))]
+fn load_extra_enemy_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let folder = bevy::asset::io::file::FileAssetReader::get_base_path()
+        .join("assets")
+        .join(EXTRA_ENEMIES_FOLDER);
+    if folder.is_dir() {
+        info!("Loading extra enemy definitions from {}", folder.display());
+        commands.insert_resource(ExtraEnemyDefinitions(
+            asset_server.load_folder(EXTRA_ENEMIES_FOLDER),
+        ));
+    }
+}
+
+/// Keeps the `EnemyList` up to date as definitions load or change. Definitions sharing a name
+/// replace each other.
+fn enemy_definitions_fill_enemy_list(
+    mut evr: EventReader<AssetEvent<EnemyDefinition>>,
+    definitions: Res<Assets<EnemyDefinition>>,
+    mut enemy_list: ResMut<EnemyList>,
 ) {
-    config.timer.tick(time.delta());
-
-    if config.timer.finished() {
-        let mut rng = rand::thread_rng();
-
-        for eid in enemy_data_pool.0.iter() {
-            // Only spawn enemies if they can spawn at the current difficulty
-            if eid.required_difficulty <= difficulty_config.difficulty_level {
-                for _ in 0..(difficulty_config.enemies_per_spawn_batch as i32
-                    * difficulty_config.modifier as i32)
-                {
-                    // Get random point on edge of spawn circle
-                    let random_angle: f32 = rng.gen_range(0.0..=1000.) * PI * 2.;
-                    let x = random_angle.cos() * config.spawn_radius.x;
-                    let y = random_angle.sin() * config.spawn_radius.y;
-
-                    // Get random monster(s) stats
-                    //let random_speed: f32 = rng.gen_range(50.0..100.0) * difficulty_config.modifier;
-                    let random_speed: f32 =
-                        rng.gen_range(eid.speed_range.clone()) * difficulty_config.modifier;
-                    let random_health: f32 =
-                        rng.gen_range(eid.health_range.clone()) * difficulty_config.modifier;
-
-                    commands.spawn(EnemyBundle {
-                        collider: Collider { radius: 32. },
-                        movement_bundle: MovementBundle {
-                            speed: Speed(random_speed),
-                            ..Default::default()
-                        },
-                        sprite_bundle: SpriteBundle {
-                            texture: eid.sprite.clone_weak(),
-                            transform: Transform::from_translation(Vec3::new(x, y, 0.)),
-                            ..Default::default()
-                        },
-                        movement_cooldown: MovementCooldown(Timer::new(
-                            Duration::from_secs(1),
-                            TimerMode::Repeating,
-                        )),
-                        health: Health(random_health),
-                        ..Default::default()
-                    });
-                }
-            }
-        }
+    for ev in evr.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = ev else {
+            continue;
+        };
+        let Some(definition) = definitions.get(*id) else {
+            continue;
+        };
+
+        enemy_list
+            .0
+            .insert(definition.name.clone(), definition.data.clone());
     }
 }
 
-fn debug_enemies(q: Query<(&Enemy, &Transform)>, mut gizmos: Gizmos) {
-    for (e, t) in q.iter() {
-        gizmos.circle_2d(t.translation.xy(), 16., Color::RED);
+/// How much damage an enemy deals when it touches the tower
+#[derive(Component, Default)]
+pub struct ContactDamage(pub f32);
+
+/// How often an enemy can damage the tower by touching it
+pub const CONTACT_DAMAGE_COOLDOWN: Duration = Duration::from_secs(1);
+
+/// Spawns one enemy of a type at a position, scaled by the current difficulty and shrunk, sped
+/// up and weakened by its generation
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    eid: &EnemyInitData,
+    position: Vec2,
+    difficulty_config: &DifficultyConfig,
+    generation: Generation,
+) -> Entity {
+    let mut rng = rand::thread_rng();
+
+    // Get random monster(s) stats
+    let random_speed: f32 = rng.gen_range(eid.speed_range.clone());
+    let health_multiplier = difficulty_config.modifier * generation.health;
+    let random_health: f32 = rng.gen_range(eid.health_range.clone()) * health_multiplier;
+    let random_damage: f32 = rng.gen_range(eid.damage_range.clone()) * difficulty_config.modifier;
+    let xp_drop = (eid.xp_drop as f32 * generation.experience).round() as u32;
+
+    let mut modifier = difficulty_speed_modifier(difficulty_config.modifier);
+    if generation.speed != 1. {
+        modifier.insert(SpeedModifier::multiply("generation", generation.speed));
+    }
+
+    let mut enemy = commands.spawn(EnemyBundle {
+        collider: Collider::circle(eid.collider_radius * generation.scale),
+        layers: ENEMY_LAYERS,
+        movement_bundle: MovementBundle {
+            speed: Speed(random_speed),
+            modifier,
+            ..Default::default()
+        },
+        sprite_bundle: SpriteBundle {
+            texture: eid.sprite.clone_weak(),
+            transform: Transform::from_translation(position.extend(0.))
+                .with_scale(Vec2::splat(generation.scale).extend(1.)),
+            ..Default::default()
+        },
+        movement_cooldown: MovementCooldown(Timer::new(
+            Duration::from_secs(1),
+            TimerMode::Repeating,
+        )),
+        health: Health(random_health),
+        max_health: MaxHealth(random_health),
+        resistances: eid.resistances.clone(),
+        armor: eid.armor.clone(),
+        mass: eid.mass,
+        // Heavier enemies take longer to get going and to turn
+        acceleration: Acceleration(ENEMY_THRUST / eid.mass.0.max(0.1)),
+        drag: Drag(ENEMY_DRAG),
+        steering: Steering::new(eid.steering),
+        contact_damage: ContactDamage(random_damage),
+        experience_drop: ExperienceDrop(xp_drop),
+        generation,
+        damage_cooldown: DamageCooldown(CONTACT_DAMAGE_COOLDOWN),
+        ..Default::default()
+    });
+
+    if let Some(ranged) = &eid.ranged {
+        enemy.insert(ranged.components(difficulty_config.modifier));
+    }
+    if let Some(split) = &eid.split {
+        enemy.insert(Splits(split.clone()));
     }
+    if let Some(shield) = &eid.shield {
+        enemy.insert(shield.component(health_multiplier));
+    }
+
+    enemy.id()
 }
 
+/// Harder difficulties make enemies faster
+fn difficulty_speed_modifier(difficulty: f32) -> MovementModifier {
+    let mut modifier = MovementModifier::default();
+    modifier.insert(SpeedModifier::multiply("difficulty", difficulty));
+    modifier
+}
+
+/// Points every enemy's steering at the tower
 fn enemies_hate_the_tower(
-    mut enemy_q: Query<(&Enemy, &Transform, &mut Velocity, &mut MovementCooldown)>,
-    tower_q: Query<(&Tower, &Transform)>,
+    mut enemy_q: Query<(
+        &Enemy,
+        &mut Steering,
+        &mut MovementCooldown,
+        Option<&StatusEffects>,
+    )>,
+    tower_q: Query<(Entity, &Tower)>,
     time: Res<Time>,
 ) {
-    let (_, tower_transform) = tower_q.single();
+    let (tower, _) = tower_q.single();
 
-    for (_, enemy_transform, mut velocity, mut mc) in enemy_q.iter_mut() {
+    for (_, mut steering, mut mc, status_effects) in enemy_q.iter_mut() {
         mc.0.tick(time.delta());
 
-        if mc.0.finished() {
-            let movement_vector =
-                tower_transform.translation.xy() - enemy_transform.translation.xy();
-            velocity.0 = movement_vector.normalize_or_zero();
+        // Stunned enemies keep whatever heading they had
+        steering.paused = status_effects.is_some_and(|s| s.suppresses_targeting());
+
+        if mc.0.finished() || steering.target.is_none() {
+            steering.target = Some(tower);
         }
     }
 }
 
+/// Enemies touching the tower damage it, rate limited by their `DamageCooldown`
 pub fn enemies_damage_the_tower(
-    eq: Query<(&Enemy, &Collider, &Transform)>,
-    mut tq: Query<(&Tower, &Collider, &Transform, &mut Health)>,
+    mut evr: EventReader<Colliding>,
+    tq: Query<(), With<Tower>>,
+    cdq: Query<&ContactDamage, With<Enemy>>,
+    mut dewr: EventWriter<DamageEvent>,
 ) {
-    let (_, tc, tt, mut th) = tq.single_mut();
-    for (_, ec, et) in eq.iter() {
-        if tc.collides_with(tt, ec, et) {
-            th.0 -= 0.1;
-        }
+    for ev in evr.read() {
+        let Some((tower, e)) = ev.ordered(|e| tq.contains(e)) else {
+            continue;
+        };
+        let Ok(cd) = cdq.get(e) else {
+            continue;
+        };
+
+        dewr.send(DamageEvent {
+            amount: cd.0,
+            target: tower,
+            kind: DamageKind::Blunt,
+            source: Some(e),
+        });
     }
 }
 
@@ -188,20 +379,45 @@ fn flip_enemy_sprite_with_velocity(mut eq: Query<(&Enemy, &Velocity, &mut Sprite
     }
 }
 
+/// Sent when an enemy dies, crediting the kill to whatever landed the killing blow
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub enemy: Entity,
+    pub killer: Option<Entity>,
+    /// Children split off other enemies are credited separately from their parents
+    pub generation: u32,
+    /// How many bug cores it dropped
+    pub experience: u32,
+}
+
 fn enemies_die(
-    eq: Query<(Entity, &Enemy, &Health, &Transform)>,
+    mut evr: EventReader<DeathEvent>,
+    eq: Query<(&Enemy, &Transform, &ExperienceDrop, &Generation)>,
     mut commands: Commands,
     assets: Res<AppAssets>,
+    mut ekwr: EventWriter<EnemyKilled>,
 ) {
     let mut rng = thread_rng();
-    for (e, _, h, et) in eq.iter() {
-        if h.0 <= 0. {
-            // Despawn the entity
-            commands.entity(e).despawn_recursive();
+    for death in evr.read() {
+        let Ok((_, et, drop, generation)) = eq.get(death.entity) else {
+            continue;
+        };
+
+        ekwr.send(EnemyKilled {
+            enemy: death.entity,
+            killer: death.killer,
+            generation: generation.number,
+            experience: drop.0,
+        });
+
+        // Despawn the entity
+        commands.entity(death.entity).despawn_recursive();
 
-            // Drop some experience
+        // Drop some experience
+        for _ in 0..drop.0 {
             commands.spawn(ExperienceBundle {
-                collider: Collider { radius: 16. },
+                collider: Collider::circle(16.),
+                layers: EXPERIENCE_LAYERS,
                 sprite_bundle: SpriteBundle {
                     texture: assets.bug_core.clone_weak(),
                     transform: Transform::from_xyz(
@@ -0,0 +1,212 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Deserializer};
+
+/// Where a group of enemies appears around the spawn ellipse.
+///
+/// Angles are in degrees, counter-clockwise from the right, written as plain numbers like
+/// `Cluster(angle: 90.0, spread: 64.0)`. Any left out are picked from the group's seed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+pub enum SpawnPattern {
+    /// Anywhere on the spawn ellipse
+    #[default]
+    Random,
+    /// Bunched up around one point on the ellipse
+    Cluster {
+        #[serde(default, deserialize_with = "some_angle")]
+        angle: Option<f32>,
+        /// How far from the point each enemy can land
+        spread: f32,
+    },
+    /// A straight line along the ellipse, filled in from one end to the other
+    Line {
+        #[serde(default, deserialize_with = "some_angle")]
+        angle: Option<f32>,
+        length: f32,
+    },
+    /// Evenly spaced all the way around
+    Ring,
+    /// Evenly spaced over part of the ellipse, coming in from one side
+    Arc {
+        #[serde(default, deserialize_with = "some_angle")]
+        angle: Option<f32>,
+        /// How much of the ellipse it covers, in degrees
+        width: f32,
+    },
+    /// Winding inwards around the ellipse, best spread out with a spawn interval
+    Spiral {
+        /// How many times it goes round from the first enemy to the last
+        turns: f32,
+    },
+}
+
+/// How far outside the spawn ellipse a spiral starts
+const SPIRAL_START_SCALE: f32 = 1.25;
+
+impl SpawnPattern {
+    /// Where the `index`th of `count` enemies spawns, on an ellipse with the given radii. The
+    /// same seed always gives the same positions, so a group shares one seed.
+    pub fn position(&self, index: u32, count: u32, spawn_radius: Vec2, seed: u64) -> Vec2 {
+        // The side the whole group comes from, and some jitter for this enemy alone
+        let anchor = StdRng::seed_from_u64(seed).gen_range(0.0..TAU);
+        let mut rng = StdRng::seed_from_u64(spawn_seed(seed, index));
+        // How far through the group this enemy is, from 0 to 1
+        let progress = if count > 1 {
+            index as f32 / (count - 1) as f32
+        } else {
+            0.5
+        };
+        let on_ellipse = |angle: f32| Vec2::new(angle.cos(), angle.sin()) * spawn_radius;
+        let angle_or_anchor = |angle: Option<f32>| angle.map_or(anchor, f32::to_radians);
+
+        match *self {
+            SpawnPattern::Random => on_ellipse(rng.gen_range(0.0..TAU)),
+            SpawnPattern::Cluster { angle, spread } => {
+                let offset = Vec2::from_angle(rng.gen_range(0.0..TAU))
+                    * spread
+                    * rng.gen_range(0.0_f32..=1.).sqrt();
+                on_ellipse(angle_or_anchor(angle)) + offset
+            }
+            SpawnPattern::Line { angle, length } => {
+                let angle = angle_or_anchor(angle);
+                // Tangent to the ellipse at the middle of the line
+                let tangent = Vec2::new(-angle.sin(), angle.cos()) * spawn_radius;
+                let tangent = tangent.normalize_or_zero();
+                on_ellipse(angle) + tangent * (progress - 0.5) * length
+            }
+            SpawnPattern::Ring => on_ellipse(anchor + TAU * index as f32 / count.max(1) as f32),
+            SpawnPattern::Arc { angle, width } => {
+                let width = width.to_radians();
+                on_ellipse(angle_or_anchor(angle) + (progress - 0.5) * width)
+            }
+            SpawnPattern::Spiral { turns } => {
+                let scale = SPIRAL_START_SCALE + (1. - SPIRAL_START_SCALE) * progress;
+                on_ellipse(anchor + progress * turns * TAU) * scale
+            }
+        }
+    }
+}
+
+/// Lets an optional angle be written as a plain number rather than `Some(..)`
+fn some_angle<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f32>, D::Error> {
+    f32::deserialize(deserializer).map(Some)
+}
+
+/// Mixes an enemy's index into its group's seed, so each one gets its own random numbers
+fn spawn_seed(seed: u64, index: u32) -> u64 {
+    (seed ^ index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: Vec2 = Vec2::splat(100.);
+
+    fn positions(pattern: SpawnPattern, count: u32, seed: u64) -> Vec<Vec2> {
+        (0..count)
+            .map(|i| pattern.position(i, count, RADIUS, seed))
+            .collect()
+    }
+
+    fn angle(position: Vec2) -> f32 {
+        position.y.atan2(position.x)
+    }
+
+    /// The angle from one position to the next, going counter-clockwise
+    fn gaps(positions: &[Vec2]) -> Vec<f32> {
+        positions
+            .windows(2)
+            .map(|w| (angle(w[1]) - angle(w[0])).rem_euclid(TAU))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_positions() {
+        for pattern in [
+            SpawnPattern::Random,
+            SpawnPattern::Cluster {
+                angle: None,
+                spread: 50.,
+            },
+            SpawnPattern::Line {
+                angle: None,
+                length: 200.,
+            },
+            SpawnPattern::Ring,
+            SpawnPattern::Arc {
+                angle: None,
+                width: 90.,
+            },
+            SpawnPattern::Spiral { turns: 2. },
+        ] {
+            assert_eq!(positions(pattern, 8, 42), positions(pattern, 8, 42));
+        }
+
+        assert_ne!(
+            positions(SpawnPattern::Random, 8, 1),
+            positions(SpawnPattern::Random, 8, 2)
+        );
+    }
+
+    #[test]
+    fn ring_is_evenly_spaced() {
+        let ring = positions(SpawnPattern::Ring, 6, 7);
+        for gap in gaps(&ring) {
+            assert!((gap - TAU / 6.).abs() < 1e-4, "{gap}");
+        }
+        for p in ring {
+            assert!((p.length() - 100.).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn arc_is_evenly_spaced_over_its_width() {
+        let arc = SpawnPattern::Arc {
+            angle: Some(90.),
+            width: 60.,
+        };
+        let arc = positions(arc, 5, 7);
+        for gap in gaps(&arc) {
+            assert!((gap - 15_f32.to_radians()).abs() < 1e-4, "{gap}");
+        }
+        assert!((angle(arc[0]) - 60_f32.to_radians()).abs() < 1e-4);
+        assert!((angle(arc[4]) - 120_f32.to_radians()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn line_covers_its_length() {
+        let line = SpawnPattern::Line {
+            angle: Some(0.),
+            length: 240.,
+        };
+        let line = positions(line, 4, 7);
+        assert!((line[0].distance(line[3]) - 240.).abs() < 1e-3);
+        for w in line.windows(2) {
+            assert!((w[0].distance(w[1]) - 80.).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn angles_are_plain_numbers_in_ron() {
+        let pattern: SpawnPattern = ron::from_str("Cluster(angle: 90.0, spread: 64.0)").unwrap();
+        assert_eq!(
+            pattern,
+            SpawnPattern::Cluster {
+                angle: Some(90.),
+                spread: 64.
+            }
+        );
+
+        let pattern: SpawnPattern = ron::from_str("Arc(width: 45.0)").unwrap();
+        assert_eq!(
+            pattern,
+            SpawnPattern::Arc {
+                angle: None,
+                width: 45.
+            }
+        );
+    }
+}
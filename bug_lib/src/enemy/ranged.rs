@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    collision::Collider,
+    combat::prelude::{DamageKind, StatusEffectData, StatusEffects},
+    effects::{HitEffect, HitEffectEvent},
+    movement::{MovementBundle, MovementModifier, Speed, SpeedModifier},
+    projectile::{
+        Lifetime, ProjectileBundle, ProjectileDamage, ProjectileEmitter, PROJECTILE_LAYERS,
+    },
+    steering::Steering,
+};
+
+/// How an enemy that shoots from a distance attacks, as written in its definition
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RangedAttackData {
+    /// How close it gets to its target before stopping to shoot
+    pub range: f32,
+    /// Seconds spent winding up after stopping, before the first volley
+    pub windup: f32,
+    /// Seconds between volleys
+    pub cooldown: f32,
+    pub damage: f32,
+    #[serde(default = "default_damage_kind")]
+    pub damage_kind: DamageKind,
+    /// Projectiles per volley
+    #[serde(default = "default_amount")]
+    pub amount: u32,
+    /// Degrees the volley fans out over
+    #[serde(default)]
+    pub spread: f32,
+    pub projectile_speed: f32,
+    #[serde(default = "default_projectile_radius")]
+    pub projectile_radius: f32,
+    /// Applied to whatever a projectile hits
+    #[serde(default)]
+    pub status: Option<StatusEffectData>,
+}
+
+fn default_damage_kind() -> DamageKind {
+    DamageKind::Poison
+}
+
+fn default_amount() -> u32 {
+    1
+}
+
+fn default_projectile_radius() -> f32 {
+    6.
+}
+
+/// Spit green, so it stands out against the background
+const PROJECTILE_COLOR: Color = Color::rgb(0.6, 0.9, 0.2);
+
+/// Name of the speed modifier that holds a ranged enemy still while it shoots
+const HOLD_POSITION_SPEED_MODIFIER: &str = "hold position";
+
+/// Stops an enemy at a distance from its `Steering` target and fires its `ProjectileEmitter`
+/// once it's wound up
+#[derive(Component, Debug, Clone)]
+pub struct RangedAttack {
+    pub range: f32,
+    pub windup: Timer,
+    /// Whether the windup glow has been shown since it last came into range
+    pub telegraphed: bool,
+}
+
+impl RangedAttackData {
+    /// The components that make an enemy shoot, with damage scaled by the difficulty
+    pub fn components(&self, damage_multiplier: f32) -> (RangedAttack, ProjectileEmitter) {
+        let projectile = ProjectileBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: PROJECTILE_COLOR,
+                    custom_size: Some(Vec2::splat(self.projectile_radius * 2.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            movement_bundle: MovementBundle {
+                speed: Speed(self.projectile_speed),
+                ..Default::default()
+            },
+            collider: Collider::circle(self.projectile_radius),
+            layers: PROJECTILE_LAYERS,
+            damage: ProjectileDamage {
+                amount: self.damage * damage_multiplier,
+                kind: self.damage_kind,
+                status: self.status,
+            },
+            // Long enough to cross the range and then some
+            lifetime: Lifetime::new(Duration::from_secs_f32(
+                self.range * 2. / self.projectile_speed,
+            )),
+            ..Default::default()
+        };
+
+        (
+            RangedAttack {
+                range: self.range,
+                windup: Timer::from_seconds(self.windup, TimerMode::Once),
+                telegraphed: false,
+            },
+            ProjectileEmitter::new(
+                projectile,
+                self.amount,
+                Duration::from_secs_f32(self.cooldown),
+            )
+            .with_spread(self.spread.to_radians()),
+        )
+    }
+}
+
+/// Ranged enemies walk until their target is in range, then hold still, wind up and shoot
+pub fn ranged_enemies_keep_their_distance(
+    mut q: Query<(
+        Entity,
+        &Transform,
+        &Steering,
+        &mut RangedAttack,
+        &mut ProjectileEmitter,
+        &mut MovementModifier,
+        Option<&StatusEffects>,
+    )>,
+    tq: Query<&Transform>,
+    time: Res<Time>,
+    mut hewr: EventWriter<HitEffectEvent>,
+) {
+    for (e, t, steering, mut ranged, mut emitter, mut modifier, status_effects) in q.iter_mut() {
+        let target = steering
+            .target
+            .and_then(|target| Some((target, tq.get(target).ok()?)));
+        let in_range = target.is_some_and(|(_, tt)| {
+            t.translation.xy().distance(tt.translation.xy()) <= ranged.range
+        });
+        emitter.target = target.map(|(target, _)| target);
+
+        let holding = modifier.get(HOLD_POSITION_SPEED_MODIFIER).is_some();
+        if in_range && !holding {
+            modifier.insert(SpeedModifier::multiply(HOLD_POSITION_SPEED_MODIFIER, 0.));
+        } else if !in_range && holding {
+            modifier.remove(HOLD_POSITION_SPEED_MODIFIER);
+        }
+
+        // Getting knocked out of range or stunned means starting over
+        let stunned = status_effects.is_some_and(|s| s.suppresses_targeting());
+        if !in_range || stunned {
+            ranged.windup.reset();
+            ranged.telegraphed = false;
+            emitter.enabled = false;
+            continue;
+        }
+        if emitter.enabled {
+            continue;
+        }
+
+        // Glow while winding up so the player can see the shot coming. Only once, time can
+        // stand still during a hit stop.
+        if !ranged.telegraphed {
+            ranged.telegraphed = true;
+            hewr.send(HitEffectEvent {
+                target: e,
+                effect: HitEffect::flash(PROJECTILE_COLOR, ranged.windup.duration()),
+            });
+        }
+
+        if ranged.windup.tick(time.delta()).finished() {
+            emitter.enabled = true;
+            emitter.fire_now();
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{combat::DeathEvent, game::DifficultyConfig, movement::Momentum};
+
+use super::{spawn_enemy, Enemy, EnemyList};
+
+/// How an enemy breaks apart when it dies, as written in its definition
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SplitData {
+    /// Name of the enemy it splits into, which can be itself
+    pub into: String,
+    pub count: u32,
+    /// How many times in a row it can split, counting from the original enemy
+    #[serde(default = "default_max_generations")]
+    pub max_generations: u32,
+    /// How hard the children get flung away from where it died
+    #[serde(default = "default_scatter")]
+    pub scatter: f32,
+    /// How much smaller each child is than its parent
+    #[serde(default = "default_shrink")]
+    pub shrink: f32,
+    /// How much faster each child is than its parent
+    #[serde(default = "default_speedup")]
+    pub speedup: f32,
+    /// How much of its parent's health each child has
+    #[serde(default = "default_weaken")]
+    pub weaken: f32,
+    /// How much of its parent's experience each child drops
+    #[serde(default = "default_experience_share")]
+    pub experience_share: f32,
+}
+
+fn default_max_generations() -> u32 {
+    1
+}
+
+fn default_scatter() -> f32 {
+    400.
+}
+
+fn default_shrink() -> f32 {
+    0.75
+}
+
+fn default_speedup() -> f32 {
+    1.25
+}
+
+fn default_weaken() -> f32 {
+    0.5
+}
+
+fn default_experience_share() -> f32 {
+    0.5
+}
+
+/// Splits the enemy into children when it dies
+#[derive(Component, Debug, Clone)]
+pub struct Splits(pub SplitData);
+
+/// How many splits an enemy is away from the one that was originally spawned, and how that
+/// has changed it
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Generation {
+    /// Zero for enemies that weren't split off anything
+    pub number: u32,
+    /// Multiplies the size of the enemy and its collider
+    pub scale: f32,
+    /// Multiplies its speed
+    pub speed: f32,
+    /// Multiplies its health
+    pub health: f32,
+    /// Multiplies how many bug cores it drops
+    pub experience: f32,
+}
+
+impl Default for Generation {
+    fn default() -> Self {
+        Generation {
+            number: 0,
+            scale: 1.,
+            speed: 1.,
+            health: 1.,
+            experience: 1.,
+        }
+    }
+}
+
+impl Generation {
+    /// The generation of the children split off an enemy of this one
+    pub fn next(&self, split: &SplitData) -> Self {
+        Generation {
+            number: self.number + 1,
+            scale: self.scale * split.shrink,
+            speed: self.speed * split.speedup,
+            health: self.health * split.weaken,
+            experience: self.experience * split.experience_share,
+        }
+    }
+}
+
+/// Dying enemies that can still split scatter their children around where they fell
+pub fn enemies_split(
+    mut evr: EventReader<DeathEvent>,
+    eq: Query<(&Transform, &Splits, &Generation), With<Enemy>>,
+    enemy_list: Res<EnemyList>,
+    difficulty_config: Res<DifficultyConfig>,
+    mut commands: Commands,
+) {
+    let mut rng = rand::thread_rng();
+    for death in evr.read() {
+        let Ok((t, splits, generation)) = eq.get(death.entity) else {
+            continue;
+        };
+        let split = &splits.0;
+        if generation.number >= split.max_generations {
+            continue;
+        }
+        let Some(eid) = enemy_list.0.get(&split.into) else {
+            warn!(
+                "Can't split into `{}`, there's no enemy by that name",
+                split.into
+            );
+            continue;
+        };
+
+        let child_generation = generation.next(split);
+        let position = t.translation.xy();
+        // Evenly spaced around a circle, turned randomly so splits don't all look the same
+        let offset = rng.gen_range(0.0..TAU);
+
+        for i in 0..split.count {
+            let direction = Vec2::from_angle(offset + TAU * i as f32 / split.count as f32);
+            let child = spawn_enemy(
+                &mut commands,
+                eid,
+                position + direction * eid.collider_radius * child_generation.scale,
+                &difficulty_config,
+                child_generation,
+            );
+
+            // The same as an `Impulse`, but the child doesn't exist yet for one to find it
+            let mass = eid.mass.0.max(f32::EPSILON);
+            commands
+                .entity(child)
+                .insert(Momentum(direction * split.scatter / mass));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generations_compound() {
+        let split: SplitData = ron::from_str(r#"(into: "brood", count: 2, weaken: 0.25)"#).unwrap();
+        let grandchild = Generation::default().next(&split).next(&split);
+
+        assert_eq!(grandchild.number, 2);
+        assert_eq!(grandchild.health, 0.0625);
+        assert_eq!(grandchild.experience, 0.25);
+        assert!((grandchild.scale - 0.5625).abs() < 1e-6);
+    }
+}
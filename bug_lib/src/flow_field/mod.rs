@@ -0,0 +1,357 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    collision::{
+        prelude::{separate_colliders, CollisionLayers, Collisions, Layer},
+        Collider,
+    },
+    movement::{MovementSet, Velocity},
+    state::AppState,
+    steering::Steering,
+    ui::despawn_screen,
+};
+
+pub struct FlowFieldPlugin;
+
+impl Plugin for FlowFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FlowField::new(32., Vec2::new(1440., 840.)))
+            .add_systems(
+                OnTransition {
+                    from: AppState::MainMenu,
+                    to: AppState::InGame,
+                },
+                setup_obstacles,
+            )
+            .add_systems(
+                OnTransition {
+                    from: AppState::InGame,
+                    to: AppState::GameOver,
+                },
+                despawn_screen::<Obstacle>,
+            )
+            .add_systems(Update, flow_field_rebuilds)
+            .add_systems(
+                FixedUpdate,
+                obstacles_block_movement
+                    .in_set(MovementSet::Resolve)
+                    .after(separate_colliders),
+            );
+    }
+}
+
+/// Marks a collider that nothing can move through, and that enemies path around
+#[derive(Component, Default, Clone, Copy)]
+pub struct Obstacle;
+
+pub const OBSTACLE_LAYERS: CollisionLayers =
+    CollisionLayers::new(Layer::OBSTACLE, Layer::ENEMY | Layer::PROJECTILE);
+
+#[derive(Bundle)]
+pub struct ObstacleBundle {
+    pub marker: Obstacle,
+    pub collider: Collider,
+    pub layers: CollisionLayers,
+    pub sprite_bundle: SpriteBundle,
+}
+
+impl Default for ObstacleBundle {
+    fn default() -> Self {
+        ObstacleBundle {
+            marker: Obstacle,
+            collider: Collider::default(),
+            layers: OBSTACLE_LAYERS,
+            sprite_bundle: SpriteBundle::default(),
+        }
+    }
+}
+
+impl ObstacleBundle {
+    /// A plain box of rock
+    pub fn rock(position: Vec2, size: Vec2) -> Self {
+        ObstacleBundle {
+            collider: Collider::aabb(size),
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.35, 0.3, 0.28),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(position.extend(0.5)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Walls around the tower with a gap on every side, so enemies have to find their way in
+fn setup_obstacles(mut commands: Commands) {
+    let horizontal = Vec2::new(320., 48.);
+    let vertical = Vec2::new(48., 240.);
+    for side in [-1., 1.] {
+        commands.spawn(ObstacleBundle::rock(Vec2::new(0., side * 260.), horizontal));
+        commands.spawn(ObstacleBundle::rock(Vec2::new(side * 380., 0.), vertical));
+    }
+}
+
+/// Cost of moving to a neighboring cell, diagonals cost about √2 times as much
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+const NEIGHBORS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+/// A grid over the arena where every cell knows which way leads to its target the quickest,
+/// going around obstacles
+#[derive(Resource)]
+pub struct FlowField {
+    pub cell_size: f32,
+    /// Half the size of the area covered, centered on the world origin
+    pub half_extents: Vec2,
+    /// How much room to leave between paths and obstacles
+    pub clearance: f32,
+    size: IVec2,
+    /// What the field leads to, only agents steering towards it should follow it
+    target: Option<Entity>,
+    goal: Option<Vec2>,
+    blocked: Vec<bool>,
+    /// `u32::MAX` for cells that can't reach the goal
+    costs: Vec<u32>,
+    directions: Vec<Vec2>,
+}
+
+impl FlowField {
+    pub fn new(cell_size: f32, half_extents: Vec2) -> Self {
+        let size = (half_extents * 2. / cell_size).ceil().as_ivec2();
+        let cells = (size.x * size.y) as usize;
+        FlowField {
+            cell_size,
+            half_extents,
+            clearance: 16.,
+            size,
+            target: None,
+            goal: None,
+            blocked: vec![false; cells],
+            costs: vec![u32::MAX; cells],
+            directions: vec![Vec2::ZERO; cells],
+        }
+    }
+
+    pub fn target(&self) -> Option<Entity> {
+        self.target
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<IVec2> {
+        let cell = ((position + self.half_extents) / self.cell_size)
+            .floor()
+            .as_ivec2();
+        self.contains(cell).then_some(cell)
+    }
+
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        (cell.as_vec2() + 0.5) * self.cell_size - self.half_extents
+    }
+
+    fn contains(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all()
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y * self.size.x + cell.x) as usize
+    }
+
+    /// Which way to go from here to reach the goal, or `None` if there's no way from here
+    pub fn direction(&self, position: Vec2) -> Option<Vec2> {
+        let cell = self.cell(position)?;
+        let index = self.index(cell);
+        match self.costs[index] {
+            u32::MAX => None,
+            // Within the goal's own cell just head straight for it
+            0 => Some((self.goal? - position).normalize_or_zero()),
+            _ => Some(self.directions[index]),
+        }
+    }
+
+    /// Every cell and the way out of it, for drawing
+    pub fn iter(&self) -> impl Iterator<Item = (Vec2, Option<Vec2>, bool)> + '_ {
+        (0..self.size.y)
+            .flat_map(move |y| (0..self.size.x).map(move |x| IVec2::new(x, y)))
+            .map(|cell| {
+                let index = self.index(cell);
+                let direction = (self.costs[index] != u32::MAX).then_some(self.directions[index]);
+                (self.cell_center(cell), direction, self.blocked[index])
+            })
+    }
+
+    /// Recomputes every cell's direction from scratch, leading to `target` at `goal`
+    pub fn rebuild<'a>(
+        &mut self,
+        target: Entity,
+        goal: Vec2,
+        obstacles: impl IntoIterator<Item = (&'a Collider, &'a Transform)>,
+    ) {
+        self.target = Some(target);
+        self.goal = Some(goal);
+        self.blocked.fill(false);
+        self.costs.fill(u32::MAX);
+        self.directions.fill(Vec2::ZERO);
+
+        // Cells count as blocked if an obstacle overlaps them, padded out by the clearance
+        let probe = Collider::aabb(Vec2::splat(self.cell_size + self.clearance * 2.));
+        for (collider, transform) in obstacles {
+            let position = transform.translation.xy();
+            let reach = Vec2::splat(collider.bounding_radius() + self.clearance + self.cell_size);
+            let min = ((position - reach + self.half_extents) / self.cell_size)
+                .floor()
+                .as_ivec2()
+                .max(IVec2::ZERO);
+            let max = ((position + reach + self.half_extents) / self.cell_size)
+                .floor()
+                .as_ivec2()
+                .min(self.size - 1);
+
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let cell = IVec2::new(x, y);
+                    let center = Transform::from_translation(self.cell_center(cell).extend(0.));
+                    if probe.collides_with(&center, collider, transform) {
+                        let index = self.index(cell);
+                        self.blocked[index] = true;
+                    }
+                }
+            }
+        }
+
+        let Some(start) = self.cell(goal) else {
+            return;
+        };
+
+        // Dijkstra outwards from the goal
+        let mut queue = BinaryHeap::new();
+        let start_index = self.index(start);
+        self.costs[start_index] = 0;
+        queue.push(Reverse((0, start.x, start.y)));
+
+        while let Some(Reverse((cost, x, y))) = queue.pop() {
+            let cell = IVec2::new(x, y);
+            if cost > self.costs[self.index(cell)] {
+                continue;
+            }
+
+            let neighbors: Vec<IVec2> = self.passable_neighbors(cell).collect();
+            for next in neighbors {
+                let step = if next.x != cell.x && next.y != cell.y {
+                    DIAGONAL_COST
+                } else {
+                    STRAIGHT_COST
+                };
+                let next_index = self.index(next);
+                if cost + step < self.costs[next_index] {
+                    self.costs[next_index] = cost + step;
+                    queue.push(Reverse((cost + step, next.x, next.y)));
+                }
+            }
+        }
+
+        // Point every cell at its cheapest neighbor. Blocked cells still get a direction so
+        // anything shoved into one can find its way back out.
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                let cell = IVec2::new(x, y);
+                let best = self
+                    .passable_neighbors(cell)
+                    .filter(|next| self.costs[self.index(*next)] != u32::MAX)
+                    .min_by_key(|next| self.costs[self.index(*next)]);
+
+                let index = self.index(cell);
+                if let Some(best) = best {
+                    self.directions[index] =
+                        (self.cell_center(best) - self.cell_center(cell)).normalize();
+                    if self.blocked[index] {
+                        self.costs[index] = self.costs[self.index(best)] + STRAIGHT_COST;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Neighbors that can be walked into, without cutting the corners of blocked cells
+    fn passable_neighbors(&self, cell: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        let open = move |c: IVec2| self.contains(c) && !self.blocked[self.index(c)];
+        NEIGHBORS.into_iter().filter_map(move |offset| {
+            let next = cell + offset;
+            let diagonal = offset.x != 0 && offset.y != 0;
+            let corners_clear = !diagonal
+                || (open(cell + IVec2::new(offset.x, 0)) && open(cell + IVec2::new(0, offset.y)));
+            (open(next) && corners_clear).then_some(next)
+        })
+    }
+}
+
+/// Recomputes the flow field whenever obstacles come, go or move, or its target moves. The
+/// target is whatever the most agents are steering towards.
+fn flow_field_rebuilds(
+    mut field: ResMut<FlowField>,
+    changed: Query<(), (With<Obstacle>, Or<(Changed<Transform>, Changed<Collider>)>)>,
+    mut removed: RemovedComponents<Obstacle>,
+    obstacles: Query<(&Collider, &Transform), With<Obstacle>>,
+    agents: Query<&Steering>,
+    tq: Query<&Transform>,
+    mut counts: Local<HashMap<Entity, usize>>,
+) {
+    counts.clear();
+    for target in agents.iter().filter_map(|s| s.target) {
+        *counts.entry(target).or_default() += 1;
+    }
+    let target = counts
+        .iter()
+        .filter(|(target, _)| tq.contains(**target))
+        // Ties go to the current target, so the field doesn't flip back and forth
+        .max_by_key(|(target, count)| (**count, field.target == Some(**target)))
+        .map(|(target, _)| *target);
+    let Some((target, goal)) = target.and_then(|t| Some((t, tq.get(t).ok()?.translation.xy())))
+    else {
+        return;
+    };
+
+    let obstacles_changed = !changed.is_empty() || removed.read().count() > 0;
+    if !obstacles_changed && field.target == Some(target) && field.goal == Some(goal) {
+        return;
+    }
+
+    field.rebuild(target, goal, obstacles.iter());
+}
+
+/// Pushes anything that moves fully back out of obstacles it runs into
+pub fn obstacles_block_movement(
+    collisions: Res<Collisions>,
+    oq: Query<(&Collider, &Transform), With<Obstacle>>,
+    mut mq: Query<(&Collider, &mut Transform), (With<Velocity>, Without<Obstacle>)>,
+) {
+    for (a, b, _) in collisions.iter() {
+        let (obstacle, mover) = match (oq.contains(a), oq.contains(b)) {
+            (true, false) => (a, b),
+            (false, true) => (b, a),
+            _ => continue,
+        };
+        let (Ok((oc, ot)), Ok((mc, mut mt))) = (oq.get(obstacle), mq.get_mut(mover)) else {
+            continue;
+        };
+
+        // Worked out again, separation may have pushed the mover since the pair was found
+        if let Some(contact) = mc.contact(&mt, oc, ot) {
+            mt.translation -= (contact.normal * contact.penetration).extend(0.);
+        }
+    }
+}
@@ -8,9 +8,11 @@ use bevy::{
 
 use crate::{
     asset_loading::AppAssets,
-    combat::prelude::Health,
-    enemy::{Enemy, EnemyInitData, EnemyList, EnemyPool},
+    combat::{read_damage_events, DeathEvent},
+    enemy::{Enemy, EnemyKilled, EnemyList, EnemyPool},
+    projectile::Projectile,
     state::AppState,
+    swatter::Swatter,
     tower::Tower,
     ui::{MenuButtonAction, OnGameOverMenuScreen, despawn_screen},
     xp::Experience,
@@ -39,7 +41,12 @@ impl Plugin for GamePlugin {
             )
                 .chain(),
         )
-        .add_systems(Update, game_over.run_if(in_state(AppState::InGame)))
+        .add_systems(
+            Update,
+            game_over
+                .after(read_damage_events)
+                .run_if(in_state(AppState::InGame)),
+        )
         .add_systems(OnEnter(AppState::GameOver), setup_game_over)
         .add_systems(
             OnTransition {
@@ -52,6 +59,7 @@ impl Plugin for GamePlugin {
             Update,
             difficulty_increases_with_time.run_if(in_state(AppState::InGame)),
         )
+        .add_systems(Update, count_kills.run_if(in_state(AppState::InGame)))
         .add_systems(
             OnTransition {
                 from: AppState::AssetsLoading,
@@ -86,6 +94,26 @@ pub struct ExperienceData {
     pub current_experience: f32,
 }
 
+/// How many enemies have been killed this game and how many bug cores they dropped, by how many
+/// times they'd split
+#[derive(Resource, Default)]
+pub struct KillStats {
+    pub by_generation: Vec<u32>,
+    pub experience_by_generation: Vec<u32>,
+    /// Kills where the swatter landed the killing blow
+    pub swatted: u32,
+}
+
+impl KillStats {
+    pub fn total(&self) -> u32 {
+        self.by_generation.iter().sum()
+    }
+
+    pub fn total_experience(&self) -> u32 {
+        self.experience_by_generation.iter().sum()
+    }
+}
+
 #[derive(Resource)]
 pub struct GameTimer(pub Stopwatch);
 
@@ -96,7 +124,7 @@ pub struct GameTimerUi;
 pub struct BackgroundImage;
 
 // Does generic housekeeping stuff to set the game up
-pub fn setup_game(mut commands: Commands, assets: Res<AppAssets>) {
+pub fn setup_game(mut commands: Commands, enemy_list: Res<EnemyList>) {
     commands.insert_resource(DifficultyConfig {
         modifier: 1.0,
         difficulty_increase_timer: Timer::new(Duration::from_secs(60), TimerMode::Repeating),
@@ -104,30 +132,34 @@ pub fn setup_game(mut commands: Commands, assets: Res<AppAssets>) {
         difficulty_level: 0,
     });
 
-    let mut starting_enemy_data = Vec::new();
-
-    starting_enemy_data.push(EnemyInitData {
-        sprite: assets.enemy1_sprite.clone_weak(),
-        health_range: (50.0..100.0),
-        speed_range: (50.0..75.0),
-        required_difficulty: 0,
-        ..Default::default()
-    });
-
-    starting_enemy_data.push(EnemyInitData {
-        sprite: assets.enemy2_sprite.clone_weak(),
-        health_range: (150.0..175.),
-        speed_range: (25.0..35.0),
-        required_difficulty: 1,
-        ..Default::default()
-    });
-
-    commands.insert_resource(EnemyPool(starting_enemy_data));
+    commands.insert_resource(EnemyPool(enemy_list.by_difficulty()));
 
     commands.insert_resource(ExperienceData {
         current_experience: 0.,
         current_level: 0,
     });
+
+    commands.insert_resource(KillStats::default());
+}
+
+fn count_kills(
+    mut evr: EventReader<EnemyKilled>,
+    mut kill_stats: ResMut<KillStats>,
+    sq: Query<(), With<Swatter>>,
+) {
+    for kill in evr.read() {
+        let generation = kill.generation as usize;
+        if kill_stats.by_generation.len() <= generation {
+            kill_stats.by_generation.resize(generation + 1, 0);
+            kill_stats.experience_by_generation.resize(generation + 1, 0);
+        }
+        kill_stats.by_generation[generation] += 1;
+        kill_stats.experience_by_generation[generation] += kill.experience;
+
+        if kill.killer.is_some_and(|k| sq.contains(k)) {
+            kill_stats.swatted += 1;
+        }
+    }
 }
 
 pub fn difficulty_increases_with_time(
@@ -201,34 +233,35 @@ pub fn update_game_timer_ui(
     )
 }
 
-pub fn game_over(tq: Query<(&Tower, &Health)>, mut state: ResMut<NextState<AppState>>) {
-    let (_, hp) = tq.single();
-
-    if hp.0 <= 0. {
-        state.set(AppState::GameOver)
+pub fn game_over(
+    mut evr: EventReader<DeathEvent>,
+    tq: Query<(), With<Tower>>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    for death in evr.read() {
+        if tq.contains(death.entity) {
+            state.set(AppState::GameOver)
+        }
     }
 }
 
 /// Displays stuff once the game is over and removes all entities that we don't need/care about
 pub fn setup_game_over(
     mut commands: Commands,
-    eq: Query<Entity, With<Enemy>>,
-    tq: Query<Entity, With<Tower>>,
-    gtq: Query<Entity, With<GameTimerUi>>,
-    expq: Query<Entity, With<Experience>>,
+    q: Query<
+        Entity,
+        Or<(
+            With<Enemy>,
+            With<Tower>,
+            With<GameTimerUi>,
+            With<Experience>,
+            With<Projectile>,
+        )>,
+    >,
+    kill_stats: Res<KillStats>,
     assets: Res<AppAssets>,
 ) {
-    // Really awful
-    for e in eq.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for e in tq.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for e in gtq.iter() {
-        commands.entity(e).despawn_recursive();
-    }
-    for e in expq.iter() {
+    for e in q.iter() {
         commands.entity(e).despawn_recursive();
     }
 
@@ -239,6 +272,7 @@ pub fn setup_game_over(
                 style: Style {
                     width: Val::Percent(100.),
                     height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
                     ..Default::default()
@@ -256,6 +290,34 @@ pub fn setup_game_over(
                     color: Color::RED,
                 },
             ),));
+            let originals = kill_stats.by_generation.first().copied().unwrap_or(0);
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "{} bugs smashed ({} split off, {} swatted)",
+                    kill_stats.total(),
+                    kill_stats.total() - originals,
+                    kill_stats.swatted
+                ),
+                TextStyle {
+                    font: font.clone_weak(),
+                    font_size: 40.,
+                    color: Color::WHITE,
+                },
+            ));
+            let original_experience =
+                kill_stats.experience_by_generation.first().copied().unwrap_or(0);
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "{} bug cores dropped ({} by split off bugs)",
+                    kill_stats.total_experience(),
+                    kill_stats.total_experience() - original_experience
+                ),
+                TextStyle {
+                    font: font.clone_weak(),
+                    font_size: 40.,
+                    color: Color::WHITE,
+                },
+            ));
             parent
                 .spawn((
                     ButtonBundle {
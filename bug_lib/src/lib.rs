@@ -1,3 +1,7 @@
+// This lint usually gives bad advice in the context of Bevy -- hiding complex queries behind
+// type aliases tends to obfuscate code while offering no improvement in code cleanliness.
+#![allow(clippy::type_complexity)]
+
 use asset_loading::AssetPlugin;
 use audio::AudioPlugin;
 use bevy::{app::PluginGroupBuilder, prelude::*};
@@ -5,21 +9,29 @@ use bevy_embedded_assets::EmbeddedAssetPlugin;
 use camera::CameraPlugin;
 use collision::CollisionPlugin;
 use combat::CombatPlugin;
+use debug::DebugOverlayPlugin;
+use effects::EffectsPlugin;
 use enemy::EnemyPlugin;
+use flow_field::FlowFieldPlugin;
 use game::GamePlugin;
 use movement::MovementPlugin;
 use projectile::ProjectilePlugin;
 use state::StatePlugin;
+use steering::SteeringPlugin;
 use swatter::SwatterPlugin;
 use tower::TowerPlugin;
 use ui::UiPlugin;
+use wave::WavePlugin;
 
 mod asset_loading;
 mod audio;
 mod camera;
-mod collision;
+pub mod collision;
 mod combat;
+mod debug;
+mod effects;
 mod enemy;
+mod flow_field;
 mod game;
 mod movement;
 mod projectile;
@@ -28,6 +40,7 @@ mod steering;
 mod swatter;
 mod tower;
 mod ui;
+mod wave;
 mod xp;
 
 pub mod prelude {
@@ -49,13 +62,18 @@ impl PluginGroup for BugGamePlugins {
             .add(SwatterPlugin)
             .add(UiPlugin)
             .add(EnemyPlugin)
+            .add(WavePlugin)
             .add(MovementPlugin)
+            .add(SteeringPlugin)
+            .add(FlowFieldPlugin)
             .add(CollisionPlugin)
             .add(TowerPlugin)
             .add(GamePlugin)
             .add(CombatPlugin)
+            .add(EffectsPlugin)
             .add(AudioPlugin)
-            .add(ProjectilePlugin);
+            .add(ProjectilePlugin)
+            .add(DebugOverlayPlugin);
         group
     }
 }
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+/// Keeps the position simulated in `FixedUpdate` apart from the `Transform` that gets rendered,
+/// which is smoothed between the last two fixed ticks.
+///
+/// Outside of `PostUpdate` the `Transform` always holds the simulated position, so gameplay
+/// systems never see the smoothed one. Moving the `Transform` from `Update` counts as a teleport.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+    initialized: bool,
+}
+
+impl Interpolated {
+    /// Jumps straight to a position without smoothing
+    fn snap(&mut self, translation: Vec3) {
+        self.previous = translation;
+        self.current = translation;
+        self.initialized = true;
+    }
+}
+
+/// Puts the simulated positions back before anything else gets a look at them
+pub fn restore_simulated_transforms(mut q: Query<(&mut Interpolated, &mut Transform)>) {
+    for (mut i, mut t) in q.iter_mut() {
+        if i.initialized {
+            t.translation = i.current;
+        } else {
+            i.snap(t.translation);
+        }
+    }
+}
+
+/// Remembers where everything was at the start of a fixed tick
+pub fn record_previous_positions(mut q: Query<(&mut Interpolated, &Transform)>) {
+    for (mut i, t) in q.iter_mut() {
+        i.previous = t.translation;
+    }
+}
+
+/// Remembers where everything ended up at the end of a fixed tick
+pub fn record_simulated_positions(mut q: Query<(&mut Interpolated, &Transform)>) {
+    for (mut i, t) in q.iter_mut() {
+        if !i.initialized {
+            i.snap(t.translation);
+        }
+        i.current = t.translation;
+    }
+}
+
+/// Blends between the last two fixed ticks by how far into the next one we are
+pub fn interpolate_transforms(
+    mut q: Query<(&mut Interpolated, &mut Transform)>,
+    time: Res<Time<Fixed>>,
+) {
+    let alpha = time.overstep_percentage();
+    for (mut i, mut t) in q.iter_mut() {
+        if !i.initialized || t.translation != i.current {
+            i.snap(t.translation);
+            continue;
+        }
+
+        t.translation = i.previous.lerp(i.current, alpha);
+    }
+}
@@ -1,43 +1,281 @@
-use bevy::prelude::*;
+use std::time::Duration;
 
-use crate::state::AppState;
+use bevy::{prelude::*, transform::TransformSystem};
 
+use crate::{collision::CollisionSet, state::AppState};
+
+use self::prelude::{
+    interpolate_transforms, record_previous_positions, record_simulated_positions,
+    restore_simulated_transforms, Interpolated,
+};
+
+mod interpolation;
+
+pub mod prelude {
+    pub use super::interpolation::*;
+}
+
+/// Moves things around at a fixed rate, and smooths out how they look in between.
+///
+/// The rule for where systems go:
+/// - Anything that moves simulated things runs in `FixedUpdate`, in one of the `MovementSet`s.
+/// - `Update` is for input, UI, effects and reacting to events. It sees the same simulated
+///   `Transform`s as `FixedUpdate`, so reading positions there is fine, but `GlobalTransform` is
+///   last frame's smoothed one. Collisions are found every fixed tick, see `CollisionPlugin`.
+/// - Only rendering sees the smoothed `Transform`, written in `PostUpdate`.
 pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            FixedUpdate,
-            velocity_moves_transforms.run_if(in_state(AppState::InGame)),
-        )
-        .insert_resource(Time::<Fixed>::from_hz(240.));
+        app.add_event::<Impulse>()
+            .add_systems(PreUpdate, restore_simulated_transforms)
+            .add_systems(Update, apply_impulses)
+            .configure_sets(
+                FixedUpdate,
+                (
+                    MovementSet::RecordPrevious,
+                    MovementSet::Move,
+                    CollisionSet,
+                    MovementSet::Resolve,
+                    MovementSet::RecordSimulated,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    record_previous_positions.in_set(MovementSet::RecordPrevious),
+                    resolve_speed_modifiers
+                        .before(velocity_moves_transforms)
+                        .run_if(in_state(AppState::InGame)),
+                    velocity_moves_transforms
+                        .in_set(MovementSet::Move)
+                        .run_if(in_state(AppState::InGame)),
+                    record_simulated_positions.in_set(MovementSet::RecordSimulated),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            )
+            .insert_resource(Time::<Fixed>::from_hz(240.));
     }
 }
 
+/// The steps of every fixed tick that write to `Transform`, in order, with `CollisionSet` run
+/// between moving and resolving
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MovementSet {
+    /// Remembers where things were before they move
+    RecordPrevious,
+    /// Moves things by their velocity
+    Move,
+    /// Pushes things back out of whatever they've moved into
+    Resolve,
+    /// Remembers where things ended up, for interpolation
+    RecordSimulated,
+}
+
 #[derive(Bundle, Default, Clone)]
 pub struct MovementBundle {
     pub velocity: Velocity,
     pub speed: Speed,
+    pub modifier: MovementModifier,
+    pub effective_speed: EffectiveSpeed,
+    pub interpolated: Interpolated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedModifierKind {
+    /// Added to the base speed, in pixels per second
+    Add(f32),
+    /// Scales the speed after everything has been added
+    Multiply(f32),
+}
+
+/// One source of speed change, like a slow, a haste or the difficulty
+#[derive(Debug, Clone)]
+pub struct SpeedModifier {
+    /// Modifiers with the same name replace each other instead of stacking
+    pub name: &'static str,
+    pub kind: SpeedModifierKind,
+    /// Goes away on its own once finished, or lasts until removed
+    pub duration: Option<Timer>,
+}
+
+impl SpeedModifier {
+    pub fn add(name: &'static str, amount: f32) -> Self {
+        SpeedModifier {
+            name,
+            kind: SpeedModifierKind::Add(amount),
+            duration: None,
+        }
+    }
+
+    pub fn multiply(name: &'static str, multiplier: f32) -> Self {
+        SpeedModifier {
+            name,
+            kind: SpeedModifierKind::Multiply(multiplier),
+            duration: None,
+        }
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(Timer::new(duration, TimerMode::Once));
+        self
+    }
+}
+
+/// Every speed modifier on something that moves, so nothing has to write to `Speed` directly
+#[derive(Component, Default, Clone)]
+pub struct MovementModifier(Vec<SpeedModifier>);
+
+impl MovementModifier {
+    /// Adds a modifier, replacing any other one with the same name
+    pub fn insert(&mut self, modifier: SpeedModifier) {
+        self.remove(modifier.name);
+        self.0.push(modifier);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|m| m.name != name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SpeedModifier> {
+        self.0.iter().find(|m| m.name == name)
+    }
+
+    /// Adds everything up first, then multiplies, never going below zero
+    pub fn apply(&self, base: f32) -> f32 {
+        let (added, multiplier) =
+            self.0
+                .iter()
+                .fold((0., 1.), |(added, multiplier), m| match m.kind {
+                    SpeedModifierKind::Add(a) => (added + a, multiplier),
+                    SpeedModifierKind::Multiply(x) => (added, multiplier * x),
+                });
+
+        ((base + added) * multiplier).max(0.)
+    }
 }
 
-// A modifier for things that move
-#[derive(Component)]
-pub struct MovementModifier {}
+/// `Speed` after every `MovementModifier`, worked out each fixed tick before anything moves
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct EffectiveSpeed(pub f32);
 
+/// Top speed, in pixels per second
 #[derive(Component, Default, Clone)]
 pub struct Speed(pub f32);
 
+/// How quickly something gets up to the velocity it wants, in pixels per second squared.
+/// Without it, things change speed and direction instantly.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Acceleration(pub f32);
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Acceleration(f32::INFINITY)
+    }
+}
+
+/// Fraction of any speed over the top speed lost each second, so knockback wears off
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Drag(pub f32);
+
+/// How fast something is actually moving, in pixels per second, as opposed to the heading in
+/// `Velocity` it's trying to move at. Only things with `Acceleration` keep track of it.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct Momentum(pub Vec2);
+
+/// Shoves something, changing its momentum by the impulse divided by its mass
+#[derive(Event)]
+pub struct Impulse {
+    pub target: Entity,
+    pub impulse: Vec2,
+}
+
+/// How hard something is to push around, heavier things get moved less
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Mass(pub f32);
+
+impl Default for Mass {
+    fn default() -> Self {
+        Mass(1.)
+    }
+}
+
+/// The heading something wants to move in, up to length 1, which gets scaled by `Speed`
 #[derive(Component, Default, Debug, Clone)]
 pub struct Velocity(pub Vec2);
 
 /// Apply velocity to things that want to move.
 /// Adapted from https://bevyengine.org/examples/Games/breakout/
 pub fn velocity_moves_transforms(
-    mut query: Query<(&Velocity, &Speed, &mut Transform)>,
+    mut query: Query<(
+        &Velocity,
+        &Speed,
+        Option<&EffectiveSpeed>,
+        Option<(&Acceleration, &mut Momentum, Option<&Drag>)>,
+        &mut Transform,
+    )>,
     time: Res<Time>,
 ) {
-    for (v, s, mut t) in query.iter_mut() {
-        t.translation.x += v.0.x * s.0 * time.delta_seconds();
-        t.translation.y += v.0.y * s.0 * time.delta_seconds();
+    let dt = time.delta_seconds();
+    for (v, s, es, inertia, mut t) in query.iter_mut() {
+        let speed = es.map_or(s.0, |es| es.0);
+        let desired = v.0 * speed;
+
+        let velocity = match inertia {
+            Some((a, mut momentum, drag)) => {
+                // Anything over top speed, like knockback, bleeds off on its own
+                if let Some(drag) = drag {
+                    let excess = momentum.0.length() - speed;
+                    if excess > 0. {
+                        let kept = momentum.0.length() - excess * (drag.0 * dt).min(1.);
+                        momentum.0 = momentum.0.normalize_or_zero() * kept;
+                    }
+                }
+
+                let change = (desired - momentum.0).clamp_length_max(a.0 * dt);
+                momentum.0 += change;
+                momentum.0
+            }
+            None => desired,
+        };
+
+        t.translation += (velocity * dt).extend(0.);
+    }
+}
+
+/// Ticks down timed speed modifiers and works out everything's effective speed
+pub fn resolve_speed_modifiers(
+    mut q: Query<(&Speed, Option<&mut MovementModifier>, &mut EffectiveSpeed)>,
+    time: Res<Time>,
+) {
+    for (s, m, mut es) in q.iter_mut() {
+        let Some(mut m) = m else {
+            es.0 = s.0;
+            continue;
+        };
+
+        for modifier in m.0.iter_mut() {
+            if let Some(duration) = modifier.duration.as_mut() {
+                duration.tick(time.delta());
+            }
+        }
+        m.0.retain(|modifier| !modifier.duration.as_ref().is_some_and(|d| d.finished()));
+
+        es.0 = m.apply(s.0);
+    }
+}
+
+fn apply_impulses(mut evr: EventReader<Impulse>, mut q: Query<(&mut Momentum, Option<&Mass>)>) {
+    for e in evr.read() {
+        let Ok((mut momentum, mass)) = q.get_mut(e.target) else {
+            continue;
+        };
+
+        let mass = mass.map_or(1., |m| m.0).max(f32::EPSILON);
+        momentum.0 += e.impulse / mass;
     }
 }
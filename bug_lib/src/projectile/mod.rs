@@ -1,6 +1,20 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::{collision::Collider, movement::MovementBundle};
+use crate::{
+    collision::{
+        prelude::{CollisionLayers, CollisionStarted, Layer},
+        Collider,
+    },
+    combat::{
+        prelude::{DamageKind, Health, StatusEffectData, StatusEffectEvent},
+        read_damage_events, DamageEvent,
+    },
+    flow_field::Obstacle,
+    movement::{MovementBundle, Velocity},
+    state::AppState,
+};
 
 pub struct ProjectilePlugin;
 
@@ -8,7 +22,12 @@ impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ProjectileSpawnEvent>().add_systems(
             Update,
-            (projectile_emitters_emit, projectiles_spawn).chain(),
+            (
+                (projectile_emitters_emit, projectiles_spawn).chain(),
+                projectiles_despawn,
+                projectiles_damage_targets.before(read_damage_events),
+            )
+                .distributive_run_if(in_state(AppState::InGame)),
         );
     }
 }
@@ -16,52 +35,201 @@ impl Plugin for ProjectilePlugin {
 #[derive(Component, Default, Clone)]
 pub struct Projectile;
 
+/// Projectiles hit the tower and obstacles, and can be swatted
+pub const PROJECTILE_LAYERS: CollisionLayers = CollisionLayers::new(
+    Layer::PROJECTILE,
+    Layer::TOWER | Layer::SWATTER | Layer::OBSTACLE,
+);
+
+/// What a projectile does to whatever it hits
+#[derive(Component, Default, Clone, Copy)]
+pub struct ProjectileDamage {
+    pub amount: f32,
+    pub kind: DamageKind,
+    /// Applied on top of the damage
+    pub status: Option<StatusEffectData>,
+}
+
+/// Despawns a projectile once it runs out
+#[derive(Component, Clone)]
+pub struct Lifetime(pub Timer);
+
+impl Default for Lifetime {
+    fn default() -> Self {
+        Lifetime::new(Duration::from_secs(5))
+    }
+}
+
+impl Lifetime {
+    pub fn new(duration: Duration) -> Self {
+        Lifetime(Timer::new(duration, TimerMode::Once))
+    }
+}
+
 #[derive(Bundle, Default, Clone)]
 pub struct ProjectileBundle {
-    pub texture: Handle<Image>,
+    pub sprite_bundle: SpriteBundle,
     pub movement_bundle: MovementBundle,
     pub collider: Collider,
+    pub layers: CollisionLayers,
+    pub damage: ProjectileDamage,
+    pub lifetime: Lifetime,
     pub marker: Projectile,
 }
 
-#[derive(Component)]
-pub struct ProjectileEmitter<'a> {
-    pub projectile_bundle: &'a ProjectileBundle,
-    pub amount: i32,
+/// Fires volleys of a projectile at a target every time its timer goes off
+#[derive(Component, Clone)]
+pub struct ProjectileEmitter {
+    pub projectile_bundle: ProjectileBundle,
+    /// Projectiles per volley
+    pub amount: u32,
+    /// Angle in radians the volley fans out over
+    pub spread: f32,
     pub timer: Timer,
+    pub target: Option<Entity>,
+    /// Holds fire while unset
+    pub enabled: bool,
+}
+
+impl ProjectileEmitter {
+    pub fn new(projectile_bundle: ProjectileBundle, amount: u32, interval: Duration) -> Self {
+        ProjectileEmitter {
+            projectile_bundle,
+            amount,
+            spread: 0.,
+            timer: Timer::new(interval, TimerMode::Repeating),
+            target: None,
+            enabled: false,
+        }
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// Fires on the next update, rather than waiting out the timer
+    pub fn fire_now(&mut self) {
+        let duration = self.timer.duration();
+        self.timer.set_elapsed(duration);
+    }
 }
 
 #[derive(Event)]
 pub struct ProjectileSpawnEvent {
-    projectile_bundle: ProjectileBundle,
+    pub projectile_bundle: ProjectileBundle,
+    pub position: Vec2,
+    /// Unit vector the projectile flies along
+    pub direction: Vec2,
 }
 
 pub fn projectiles_spawn(mut evt: EventReader<ProjectileSpawnEvent>, mut commands: Commands) {
     for e in evt.read() {
-        commands.spawn((e.projectile_bundle.clone(),));
+        let mut bundle = e.projectile_bundle.clone();
+        bundle.sprite_bundle.transform = Transform::from_translation(e.position.extend(1.))
+            .with_rotation(Quat::from_rotation_z(Vec2::X.angle_between(e.direction)));
+        bundle.movement_bundle.velocity = Velocity(e.direction);
+
+        commands.spawn(bundle);
     }
 }
 
 pub fn projectile_emitters_emit(
-    mut peq: Query<&mut ProjectileEmitter<'static>>,
+    mut peq: Query<(&Transform, &mut ProjectileEmitter)>,
+    tq: Query<&Transform>,
     mut pewr: EventWriter<ProjectileSpawnEvent>,
     time: Res<Time>,
 ) {
-    for mut pe in peq.iter_mut() {
+    for (t, mut pe) in peq.iter_mut() {
+        if !pe.enabled {
+            continue;
+        }
+        let Some(Ok(target)) = pe.target.map(|target| tq.get(target)) else {
+            continue;
+        };
+
         pe.timer.tick(time.delta());
+        if !pe.timer.finished() {
+            continue;
+        }
 
-        if pe.timer.finished() {
-            for _ in 0..(pe.amount) {
-                pewr.send(ProjectileSpawnEvent {
-                    projectile_bundle: pe.projectile_bundle.clone(),
-                });
-            }
+        let position = t.translation.xy();
+        let Some(aim) = (target.translation.xy() - position).try_normalize() else {
+            continue;
+        };
+
+        for i in 0..pe.amount {
+            // Spread evenly across the fan, with a lone projectile going straight
+            let offset = if pe.amount > 1 {
+                pe.spread * (i as f32 / (pe.amount - 1) as f32 - 0.5)
+            } else {
+                0.
+            };
+
+            pewr.send(ProjectileSpawnEvent {
+                projectile_bundle: pe.projectile_bundle.clone(),
+                position,
+                direction: Vec2::from_angle(offset).rotate(aim),
+            });
         }
     }
 }
 
 /// After some time, projectiles should despawn
-pub fn projectiles_despawn() {}
+pub fn projectiles_despawn(
+    mut pq: Query<(Entity, &mut Lifetime), With<Projectile>>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (e, mut lifetime) in pq.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Projectiles damage whatever they hit, lay any status they carry on it and break on obstacles,
+/// the swatter deals with them separately. Each projectile is the source of its own damage, so a volley isn't held back by
+/// its shooter's `DamageCooldown`.
+pub fn projectiles_damage_targets(
+    mut evr: EventReader<CollisionStarted>,
+    pq: Query<&ProjectileDamage, With<Projectile>>,
+    tq: Query<(Has<Health>, Has<Obstacle>)>,
+    mut commands: Commands,
+    mut dewr: EventWriter<DamageEvent>,
+    mut sewr: EventWriter<StatusEffectEvent>,
+    mut hit: Local<Vec<Entity>>,
+) {
+    hit.clear();
+    for ev in evr.read() {
+        let Some((p, other)) = ev
+            .ordered(|e| pq.contains(e))
+            .filter(|(p, _)| !hit.contains(p))
+        else {
+            continue;
+        };
+        let (Ok(damage), Ok((has_health, is_obstacle))) = (pq.get(p), tq.get(other)) else {
+            continue;
+        };
+
+        if has_health {
+            dewr.send(DamageEvent {
+                amount: damage.amount,
+                target: other,
+                kind: damage.kind,
+                source: Some(p),
+            });
+            if let Some(status) = damage.status {
+                sewr.send(StatusEffectEvent {
+                    target: other,
+                    effect: status.effect(p),
+                });
+            }
+        } else if !is_obstacle {
+            continue;
+        }
 
-/// Projectiles do damage to entities
-pub fn projectiles_damage_enemies() {}
+        hit.push(p);
+        commands.entity(p).despawn_recursive();
+    }
+}
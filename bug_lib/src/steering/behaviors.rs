@@ -0,0 +1,82 @@
+//! Each behavior returns the direction an agent would like to be heading, scaled from 0 to 1 by
+//! how badly it wants to go there. They get weighted and added up by `apply_steering`.
+
+use bevy::prelude::*;
+
+/// Head straight for a point
+pub fn seek(position: Vec2, target: Vec2) -> Vec2 {
+    (target - position).normalize_or_zero()
+}
+
+/// Head straight away from a point
+pub fn flee(position: Vec2, threat: Vec2) -> Vec2 {
+    -seek(position, threat)
+}
+
+/// Head for a point, easing off once within `slowing_radius` so it comes to a stop there
+pub fn arrive(position: Vec2, target: Vec2, slowing_radius: f32) -> Vec2 {
+    let offset = target - position;
+    let distance = offset.length();
+    if distance <= f32::EPSILON {
+        return Vec2::ZERO;
+    }
+
+    offset / distance * (distance / slowing_radius.max(f32::EPSILON)).min(1.)
+}
+
+/// Drift around a little, `angle` is how far off the current heading the agent is wandering
+pub fn wander(heading: Vec2, angle: f32) -> Vec2 {
+    let heading = heading.try_normalize().unwrap_or(Vec2::X);
+    (heading + Vec2::from_angle(angle).rotate(heading) * 0.5).normalize_or_zero()
+}
+
+/// Head for where a moving target is going to be by the time the agent gets there
+pub fn pursue(position: Vec2, speed: f32, target: Vec2, target_velocity: Vec2) -> Vec2 {
+    seek(position, predict(position, speed, target, target_velocity))
+}
+
+/// Head away from where a moving threat is going to be
+pub fn evade(position: Vec2, speed: f32, threat: Vec2, threat_velocity: Vec2) -> Vec2 {
+    flee(position, predict(position, speed, threat, threat_velocity))
+}
+
+/// Keep some space from neighbors, pushing harder the closer they are
+pub fn separation(position: Vec2, neighbors: &[(Vec2, Vec2)], radius: f32) -> Vec2 {
+    let push = neighbors
+        .iter()
+        .filter_map(|(p, _)| {
+            let away = position - *p;
+            let distance = away.length();
+            (distance > f32::EPSILON && distance < radius)
+                .then(|| away / distance * (1. - distance / radius))
+        })
+        .sum::<Vec2>();
+
+    push.clamp_length_max(1.)
+}
+
+/// Head the same way as neighbors
+pub fn alignment(neighbors: &[(Vec2, Vec2)]) -> Vec2 {
+    neighbors
+        .iter()
+        .map(|(_, v)| v.normalize_or_zero())
+        .sum::<Vec2>()
+        .normalize_or_zero()
+}
+
+/// Head for the middle of the neighbors
+pub fn cohesion(position: Vec2, neighbors: &[(Vec2, Vec2)]) -> Vec2 {
+    if neighbors.is_empty() {
+        return Vec2::ZERO;
+    }
+
+    let center = neighbors.iter().map(|(p, _)| *p).sum::<Vec2>() / neighbors.len() as f32;
+    seek(position, center)
+}
+
+/// Where something moving will be once an agent moving at `speed` could reach it
+fn predict(position: Vec2, speed: f32, target: Vec2, target_velocity: Vec2) -> Vec2 {
+    // Looking too far ahead makes agents chase ghosts, so cap it
+    let time = (position.distance(target) / speed.max(f32::EPSILON)).min(2.);
+    target + target_velocity * time
+}
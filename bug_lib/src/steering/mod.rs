@@ -1 +1,202 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng;
+use serde::Deserialize;
 
+use crate::{
+    collision::prelude::SpatialHash,
+    flow_field::FlowField,
+    movement::{
+        resolve_speed_modifiers, velocity_moves_transforms, EffectiveSpeed, Speed, Velocity,
+    },
+};
+
+use self::prelude::{alignment, arrive, cohesion, evade, flee, pursue, seek, separation, wander};
+
+mod behaviors;
+
+pub mod prelude {
+    pub use super::behaviors::*;
+}
+
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            apply_steering
+                .after(resolve_speed_modifiers)
+                .before(velocity_moves_transforms),
+        );
+    }
+}
+
+/// How strongly each behavior pulls on an agent, zero turns a behavior off
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SteeringWeights {
+    /// Follow the `FlowField` around obstacles, or seek the target wherever it doesn't reach
+    pub flow: f32,
+    pub seek: f32,
+    pub flee: f32,
+    pub arrive: f32,
+    pub wander: f32,
+    pub pursue: f32,
+    pub evade: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+impl Default for SteeringWeights {
+    /// Just find a way to the tower
+    fn default() -> Self {
+        SteeringWeights {
+            flow: 1.,
+            seek: 0.,
+            flee: 0.,
+            arrive: 0.,
+            wander: 0.,
+            pursue: 0.,
+            evade: 0.,
+            separation: 0.,
+            alignment: 0.,
+            cohesion: 0.,
+        }
+    }
+}
+
+/// Steers an agent's `Velocity` with a weighted mix of behaviors
+#[derive(Component, Debug, Clone)]
+pub struct Steering {
+    pub weights: SteeringWeights,
+    /// What seek, arrive and pursue head towards
+    pub target: Option<Entity>,
+    /// What flee and evade run from
+    pub threat: Option<Entity>,
+    /// How quickly the velocity turns towards where the behaviors want to go, per second
+    pub agility: f32,
+    /// How close other agents have to be to count for separation, alignment and cohesion
+    pub neighbor_radius: f32,
+    /// How far out arrive starts easing off
+    pub slowing_radius: f32,
+    /// Leaves the velocity alone while set
+    pub paused: bool,
+    wander_angle: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Steering {
+            weights: SteeringWeights::default(),
+            target: None,
+            threat: None,
+            agility: 4.,
+            neighbor_radius: 96.,
+            slowing_radius: 64.,
+            paused: false,
+            wander_angle: 0.,
+        }
+    }
+}
+
+impl Steering {
+    pub fn new(weights: SteeringWeights) -> Self {
+        Steering {
+            weights,
+            ..default()
+        }
+    }
+}
+
+/// How far the wander angle can drift each second
+const WANDER_JITTER: f32 = 4.;
+
+/// Mixes every agent's behaviors together and turns its velocity towards the result
+pub fn apply_steering(
+    mut q: Query<(
+        Entity,
+        &mut Steering,
+        &Transform,
+        &mut Velocity,
+        &EffectiveSpeed,
+    )>,
+    others: Query<(&Transform, Option<&Velocity>, Option<&Speed>), Without<Steering>>,
+    hash: Res<SpatialHash>,
+    flow_field: Option<Res<FlowField>>,
+    time: Res<Time>,
+    mut agents: Local<HashMap<Entity, (Vec2, Vec2)>>,
+) {
+    // Agents can target and flock with each other, so snapshot them before changing anything
+    agents.clear();
+    for (e, _, t, v, s) in q.iter() {
+        agents.insert(e, (t.translation.xy(), v.0 * s.0));
+    }
+
+    // Where something is and how fast it's going, in pixels per second
+    let locate = |e: Entity| {
+        agents.get(&e).copied().or_else(|| {
+            let (t, v, s) = others.get(e).ok()?;
+            let velocity = v.map_or(Vec2::ZERO, |v| v.0) * s.map_or(0., |s| s.0);
+            Some((t.translation.xy(), velocity))
+        })
+    };
+
+    let mut rng = rand::thread_rng();
+    let dt = time.delta_seconds();
+    let mut neighbors = Vec::new();
+
+    for (e, mut steering, t, mut velocity, speed) in q.iter_mut() {
+        if steering.paused {
+            continue;
+        }
+
+        let position = t.translation.xy();
+        let speed = speed.0;
+        let w = steering.weights;
+
+        neighbors.clear();
+        if w.separation != 0. || w.alignment != 0. || w.cohesion != 0. {
+            neighbors.extend(
+                hash.query_circle(position, steering.neighbor_radius)
+                    .filter(|entry| entry.entity != e)
+                    .filter_map(|entry| agents.get(&entry.entity).copied()),
+            );
+        }
+
+        let mut desired = Vec2::ZERO;
+
+        if let Some((target, target_velocity)) = steering.target.and_then(locate) {
+            // The field only leads to one target, anything else is sought directly
+            let flow = flow_field
+                .as_ref()
+                .filter(|f| f.target() == steering.target)
+                .and_then(|f| f.direction(position));
+            desired += w.flow * flow.unwrap_or_else(|| seek(position, target));
+            desired += w.seek * seek(position, target);
+            desired += w.arrive * arrive(position, target, steering.slowing_radius);
+            desired += w.pursue * pursue(position, speed, target, target_velocity);
+        }
+
+        if let Some((threat, threat_velocity)) = steering.threat.and_then(locate) {
+            desired += w.flee * flee(position, threat);
+            desired += w.evade * evade(position, speed, threat, threat_velocity);
+        }
+
+        if w.wander != 0. {
+            steering.wander_angle = (steering.wander_angle
+                + rng.gen_range(-1.0..=1.0) * WANDER_JITTER * dt)
+                .clamp(-1., 1.);
+            desired += w.wander * wander(velocity.0, steering.wander_angle);
+        }
+
+        desired += w.separation * separation(position, &neighbors, steering.neighbor_radius);
+        desired += w.alignment * alignment(&neighbors);
+        desired += w.cohesion * cohesion(position, &neighbors);
+
+        // Velocity is a heading scaled by `Speed`, so it never goes past 1
+        let desired = desired.clamp_length_max(1.);
+        let turn = (steering.agility * dt).min(1.);
+        velocity.0 = velocity.0.lerp(desired, turn).clamp_length_max(1.);
+    }
+}
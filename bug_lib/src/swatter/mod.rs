@@ -9,14 +9,19 @@ use bevy::{
 
 use crate::{
     asset_loading::AppAssets,
-    collision::{visualize_colliders, Collider},
+    collision::{
+        prelude::{ColliderQuery, CollisionLayers, CollisionStarted, Layer, SpatialHash},
+        visualize_colliders, Collider,
+    },
     combat::{
-        prelude::{Flasher, Health},
-        read_damage_events, DamageEvent,
+        prelude::{CriticalStrike, DamageCooldown, DamageKind},
+        read_damage_events, DamageDealtEvent, DamageEvent,
     },
+    effects::{HitEffect, HitEffectEvent, HitStopEvent, FLASH_WHITE},
     enemy::Enemy,
     game::ExperienceData,
-    movement::velocity_moves_transforms,
+    movement::{Impulse, MovementModifier, SpeedModifier},
+    projectile::Projectile,
     state::AppState,
     xp::Experience,
 };
@@ -30,8 +35,6 @@ impl Plugin for SwatterPlugin {
             (
                 swatter_follows_mouse.before(visualize_colliders),
                 swatter_damages_enemy
-                    .after(swatter_follows_mouse)
-                    .after(velocity_moves_transforms)
                     .before(read_damage_events)
                     .run_if(in_state(AppState::InGame)),
                 apply_deferred,
@@ -51,6 +54,22 @@ impl Plugin for SwatterPlugin {
                 .run_if(in_state(AppState::InGame))
                 .after(swatter_follows_mouse),
         )
+        .add_systems(
+            Update,
+            swatter_swats_projectiles
+                .after(swatter_follows_mouse)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                swatter_hits_play_feedback,
+                swatter_knocks_back_enemies,
+                swatter_dazes_enemies,
+            )
+                .after(read_damage_events)
+                .distributive_run_if(in_state(AppState::InGame)),
+        )
         .add_systems(
             Update,
             swatter_picks_up_xp
@@ -72,7 +91,16 @@ fn setup_swatter(mut commands: Commands, mut windows: Query<&mut Window>, assets
 
     commands.spawn((
         Swatter,
-        Collider { radius: 16. },
+        Collider::circle(16.),
+        CollisionLayers::new(
+            Layer::SWATTER,
+            Layer::ENEMY | Layer::PICKUP | Layer::PROJECTILE,
+        ),
+        CriticalStrike {
+            chance: 0.1,
+            multiplier: 2.,
+        },
+        DamageCooldown(Duration::from_millis(150)),
         SpatialBundle {
             ..Default::default()
         },
@@ -135,69 +163,178 @@ pub fn swatter_follows_mouse(
     }
 }
 
+/// The kind of damage a swat deals, which tells it apart from anything else credited to the
+/// swatter
+const SWAT_DAMAGE_KIND: DamageKind = DamageKind::Blunt;
+
 fn swatter_damages_enemy(
+    enemy_query: ColliderQuery<With<Enemy>>,
+    swatter_query: ColliderQuery<With<Swatter>>,
+    hash: Res<SpatialHash>,
+    buttons: Res<Input<MouseButton>>,
+    mut dewr: EventWriter<DamageEvent>,
+) {
+    if buttons.just_pressed(MouseButton::Left) {
+        for (swatter, e) in hash.pairs_between(&swatter_query, &enemy_query) {
+            dewr.send(DamageEvent {
+                amount: 100.,
+                target: e,
+                kind: SWAT_DAMAGE_KIND,
+                source: Some(swatter),
+            });
+        }
+    }
+}
+
+/// Swats that actually land flash, squash and stop time for a moment, the ones eaten by a
+/// cooldown or invulnerability don't
+fn swatter_hits_play_feedback(
     mut commands: Commands,
-    mut enemy_query: Query<(Entity, &Enemy, &Collider, &Transform, &mut Health)>,
-    swatter_query: Query<(&Swatter, &Collider, &Transform)>,
+    mut evr: EventReader<DamageDealtEvent>,
+    sq: Query<(), With<Swatter>>,
+    assets: Res<AppAssets>,
+    mut hewr: EventWriter<HitEffectEvent>,
+    mut hsewr: EventWriter<HitStopEvent>,
+) {
+    for e in evr.read() {
+        if e.kind != SWAT_DAMAGE_KIND || !e.source.is_some_and(|s| sq.contains(s)) {
+            continue;
+        }
+
+        hewr.send_batch([
+            HitEffectEvent {
+                target: e.target,
+                effect: HitEffect::flash(FLASH_WHITE, Duration::from_millis(100)),
+            },
+            HitEffectEvent {
+                target: e.target,
+                effect: HitEffect::squash_stretch(0.3, Duration::from_millis(150)),
+            },
+        ]);
+        hsewr.send(HitStopEvent(Duration::from_millis(30)));
+
+        commands.spawn(AudioBundle {
+            source: assets.hit_audio.clone_weak(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+}
+
+/// Clicking on projectiles knocks them out of the air
+fn swatter_swats_projectiles(
+    mut commands: Commands,
+    projectile_query: ColliderQuery<With<Projectile>>,
+    swatter_query: ColliderQuery<With<Swatter>>,
+    hash: Res<SpatialHash>,
     buttons: Res<Input<MouseButton>>,
     assets: Res<AppAssets>,
-    mut dewr: EventWriter<DamageEvent>,
+    mut hsewr: EventWriter<HitStopEvent>,
 ) {
-    let Ok((_, swatter_collider, swatter_transform)) = swatter_query.get_single() else {
+    if !buttons.just_pressed(MouseButton::Left) {
         return;
-    };
+    }
 
-    if buttons.just_pressed(MouseButton::Left) {
-        for (e, _, enemy_collider, enemy_transform, mut health) in enemy_query.iter_mut() {
-            let collision =
-                swatter_collider.collides_with(swatter_transform, enemy_collider, enemy_transform);
-
-            if collision {
-                commands.entity(e).insert(Flasher(Timer::new(
-                    Duration::from_millis(100),
-                    TimerMode::Once,
-                )));
-
-                dewr.send(DamageEvent {
-                    amount: 100.,
-                    target: e,
-                });
-
-                commands.spawn(AudioBundle {
-                    source: assets.hit_audio.clone_weak(),
-                    settings: PlaybackSettings {
-                        mode: PlaybackMode::Despawn,
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                });
-            }
+    let mut swatted = false;
+    for (_, p) in hash.pairs_between(&swatter_query, &projectile_query) {
+        commands.entity(p).despawn_recursive();
+        swatted = true;
+    }
+
+    if swatted {
+        hsewr.send(HitStopEvent(Duration::from_millis(20)));
+        commands.spawn(AudioBundle {
+            source: assets.hit_audio.clone_weak(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                ..Default::default()
+            },
+        });
+    }
+}
+
+/// How hard each point of damage dealt by the swatter shoves its target
+const KNOCKBACK_PER_DAMAGE: f32 = 4.;
+
+/// Enemies hit by the swatter get knocked away from it
+fn swatter_knocks_back_enemies(
+    mut evr: EventReader<DamageDealtEvent>,
+    sq: Query<&Transform, With<Swatter>>,
+    eq: Query<&Transform, With<Enemy>>,
+    mut iewr: EventWriter<Impulse>,
+) {
+    for e in evr.read() {
+        if e.kind != SWAT_DAMAGE_KIND {
+            continue;
         }
+        let Some(Ok(st)) = e.source.map(|s| sq.get(s)) else {
+            continue;
+        };
+        let Ok(et) = eq.get(e.target) else {
+            continue;
+        };
+
+        let direction = (et.translation.xy() - st.translation.xy()).normalize_or_zero();
+        iewr.send(Impulse {
+            target: e.target,
+            impulse: direction * e.amount * KNOCKBACK_PER_DAMAGE,
+        });
+    }
+}
+
+/// How much speed swatted enemies lose while they get their bearings back, in pixels per second
+const SWAT_DAZE_SLOWDOWN: f32 = 25.;
+const SWAT_DAZE_DURATION: Duration = Duration::from_millis(600);
+
+/// Enemies hit by the swatter are a bit slower for a moment afterwards
+fn swatter_dazes_enemies(
+    mut evr: EventReader<DamageDealtEvent>,
+    sq: Query<(), With<Swatter>>,
+    mut eq: Query<&mut MovementModifier, With<Enemy>>,
+) {
+    for e in evr.read() {
+        if e.kind != SWAT_DAMAGE_KIND || !e.source.is_some_and(|s| sq.contains(s)) {
+            continue;
+        }
+        let Ok(mut modifier) = eq.get_mut(e.target) else {
+            continue;
+        };
+
+        // Swatting it again before it recovers starts the daze over
+        modifier.insert(
+            SpeedModifier::add("swatted", -SWAT_DAZE_SLOWDOWN).with_duration(SWAT_DAZE_DURATION),
+        );
     }
 }
 
 fn swatter_picks_up_xp(
-    sq: Query<(&Swatter, &Transform, &Collider)>,
-    expq: Query<(Entity, &Experience, &Transform, &Collider)>,
+    mut evr: EventReader<CollisionStarted>,
+    sq: Query<(), With<Swatter>>,
+    expq: Query<(), With<Experience>>,
     mut commands: Commands,
     mut experience_data: ResMut<ExperienceData>,
     assets: Res<AppAssets>,
 ) {
-    let (_, st, sc) = sq.single();
-
-    for (e, _, et, ec) in expq.iter() {
-        let collision = sc.collides_with(st, ec, et);
-        if collision {
-            commands.entity(e).despawn_recursive();
-            commands.spawn(AudioBundle {
-                source: assets.xp_audio.clone_weak(),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Despawn,
-                    ..Default::default()
-                },
-            });
-            experience_data.current_experience += 1.;
-        }
+    for ev in evr.read() {
+        let Some((_, e)) = ev
+            .ordered(|e| sq.contains(e))
+            .filter(|(_, e)| expq.contains(*e))
+        else {
+            continue;
+        };
+
+        commands.entity(e).despawn_recursive();
+        commands.spawn(AudioBundle {
+            source: assets.xp_audio.clone_weak(),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                ..Default::default()
+            },
+        });
+        experience_data.current_experience += 1.;
     }
 }
 
@@ -1,8 +1,20 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
-    asset_loading::AppAssets, collision::Collider, combat::prelude::Health,
-    enemy::enemies_damage_the_tower, state::AppState, ui::despawn_screen,
+    asset_loading::AppAssets,
+    collision::{
+        prelude::{CollisionLayers, Layer},
+        Collider,
+    },
+    combat::{
+        prelude::{Health, InvulnerableAfterHit, MaxHealth, Regeneration},
+        read_damage_events, read_heal_events, DamageDealtEvent,
+    },
+    effects::{HitEffect, HitEffectEvent},
+    state::AppState,
+    ui::despawn_screen,
 };
 
 pub struct TowerPlugin;
@@ -26,8 +38,8 @@ impl Plugin for TowerPlugin {
         .add_systems(
             Update,
             (
-                debug_tower,
-                tower_health_bar_updates.after(enemies_damage_the_tower),
+                tower_health_bar_updates.after(read_heal_events),
+                tower_hits_punch_the_health_bar.after(read_damage_events),
             )
                 .distributive_run_if(in_state(AppState::InGame)),
         );
@@ -41,26 +53,29 @@ pub struct Tower;
 pub struct TowerBundle {
     marker: Tower,
     health: Health,
+    max_health: MaxHealth,
     collider: Collider,
+    layers: CollisionLayers,
     transform: Transform,
 }
 
 /// Spawns the tower
 fn setup_tower(mut commands: Commands) {
     println!("Setting up tower!");
-    commands.spawn(TowerBundle {
-        transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
-        collider: Collider { radius: 16. },
-        health: Health(1000.),
-        ..default()
-    });
-}
-
-/// Shows a thingy to represent the tower
-fn debug_tower(q: Query<(&Tower, &Transform)>, mut gizmos: Gizmos) {
-    for (_, t) in q.iter() {
-        gizmos.circle_2d(t.translation.xy(), 16., Color::GREEN);
-    }
+    commands.spawn((
+        TowerBundle {
+            transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+            collider: Collider::circle(16.),
+            layers: CollisionLayers::new(Layer::TOWER, Layer::ENEMY | Layer::PROJECTILE),
+            health: Health(1000.),
+            max_health: MaxHealth(1000.),
+            ..default()
+        },
+        // The tower slowly repairs itself when left alone
+        Regeneration::new(10., Duration::from_secs(5)),
+        // A moment of grace after each hit, so a swarm can't shred it in a single frame
+        InvulnerableAfterHit(Duration::from_millis(250)),
+    ));
 }
 
 #[derive(Component)]
@@ -130,11 +145,40 @@ fn setup_tower_health_ui(mut commands: Commands, assets: Res<AppAssets>) {
 }
 
 fn tower_health_bar_updates(
-    thq: Query<(&Tower, &Health)>,
+    thq: Query<(&Tower, &Health, &MaxHealth)>,
     mut thbq: Query<(&TowerHealthBarUiValue, &mut Style)>,
 ) {
-    let (_, th) = thq.single();
+    let (_, th, tmh) = thq.single();
     let (_, mut s) = thbq.single_mut();
 
-    s.width = Val::Percent((th.0 / 1000.) * 10.);
+    s.width = Val::Percent(tmh.fraction(th) * 100.);
+}
+
+/// The health bar punches out and flashes red whenever a hit lands on the tower
+fn tower_hits_punch_the_health_bar(
+    mut evr: EventReader<DamageDealtEvent>,
+    tq: Query<(), With<Tower>>,
+    thbq: Query<Entity, With<TowerHealthBarUiValue>>,
+    mut hewr: EventWriter<HitEffectEvent>,
+) {
+    let Ok(bar) = thbq.get_single() else {
+        return;
+    };
+
+    for e in evr.read() {
+        if e.amount <= 0. || !tq.contains(e.target) {
+            continue;
+        }
+
+        hewr.send_batch([
+            HitEffectEvent {
+                target: bar,
+                effect: HitEffect::scale_punch(0.05, Duration::from_millis(150)),
+            },
+            HitEffectEvent {
+                target: bar,
+                effect: HitEffect::flash(Color::RED, Duration::from_millis(150)),
+            },
+        ]);
+    }
 }
@@ -0,0 +1,372 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    asset_loading::AppAssets,
+    enemy::{prelude::Generation, spawn_enemy, EnemyList, EnemyPool, EnemySpawnConfig},
+    game::DifficultyConfig,
+    state::AppState,
+};
+
+use self::prelude::{Wave, WaveScript, WaveScriptLoader};
+
+mod script;
+
+pub mod prelude {
+    pub use super::script::*;
+}
+
+/// Runs the scripted waves from `AppAssets::waves`, then keeps spawning batches on a timer once
+/// they run out
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WaveScript>()
+            .init_asset_loader::<WaveScriptLoader>()
+            .init_resource::<WaveDirector>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .add_systems(
+                OnTransition {
+                    from: AppState::MainMenu,
+                    to: AppState::InGame,
+                },
+                reset_wave_director,
+            )
+            .add_systems(Update, waves_advance.run_if(in_state(AppState::InGame)))
+            .add_systems(Update, (show_wave_banners, wave_banners_fade).chain());
+    }
+}
+
+/// Sent when a wave starts spawning, numbered from one
+#[derive(Event)]
+pub struct WaveStarted {
+    pub number: u32,
+    /// The fallback wave after the script runs out, which never gets cleared
+    pub endless: bool,
+}
+
+/// Sent once every enemy from a wave is dead
+#[derive(Event)]
+pub struct WaveCleared {
+    pub number: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum WavePhase {
+    /// Calm before the next wave
+    Resting(Timer),
+    /// Enemies from the current wave are still arriving
+    Spawning {
+        /// Seconds since the wave started
+        elapsed: f32,
+        /// How many of each group have spawned so far
+        spawned: Vec<u32>,
+        /// Where each group's pattern lands
+        seeds: Vec<u64>,
+    },
+    /// Everything has spawned, waiting for the last of the wave's own enemies to die
+    Clearing,
+    /// Out of scripted waves, spawning a batch every `EnemySpawnConfig::timer`
+    Endless,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct WaveDirector {
+    /// The current wave, zero before the first one starts
+    pub number: u32,
+    pub phase: WavePhase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        WaveDirector {
+            number: 0,
+            phase: WavePhase::Resting(Timer::default()),
+        }
+    }
+}
+
+/// Marks an enemy spawned by a scripted wave, which has to die before the wave is cleared.
+/// Anything it splits into, and anything spawned in endless mode, doesn't hold the wave up.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct WaveEnemy {
+    pub wave: u32,
+}
+
+/// The rest before a wave, or none if the script has run out
+fn rest_before(wave: Option<&Wave>) -> Timer {
+    let rest = wave.map_or(0., |w| w.rest);
+    Timer::from_seconds(rest, TimerMode::Once)
+}
+
+fn reset_wave_director(
+    mut director: ResMut<WaveDirector>,
+    scripts: Res<Assets<WaveScript>>,
+    assets: Res<AppAssets>,
+    enemy_list: Res<EnemyList>,
+) {
+    let script = scripts.get(&assets.waves);
+    if let Some(Err(e)) = script.map(|s| s.check_enemies(|name| enemy_list.0.contains_key(name))) {
+        error!("{e}");
+    }
+
+    let first = script.and_then(|s| s.waves.first());
+    *director = WaveDirector {
+        number: 0,
+        phase: WavePhase::Resting(rest_before(first)),
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+fn waves_advance(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    scripts: Res<Assets<WaveScript>>,
+    assets: Res<AppAssets>,
+    enemy_list: Res<EnemyList>,
+    enemy_pool: Res<EnemyPool>,
+    mut spawn_config: ResMut<EnemySpawnConfig>,
+    difficulty_config: Res<DifficultyConfig>,
+    wq: Query<&WaveEnemy>,
+    time: Res<Time>,
+    mut wswr: EventWriter<WaveStarted>,
+    mut wcwr: EventWriter<WaveCleared>,
+) {
+    let waves = scripts.get(&assets.waves).map_or(&[][..], |s| &s.waves[..]);
+    let director = &mut *director;
+    // Waves are numbered from one
+    let current = director
+        .number
+        .checked_sub(1)
+        .and_then(|i| waves.get(i as usize));
+
+    match &mut director.phase {
+        WavePhase::Resting(timer) => {
+            if !timer.tick(time.delta()).finished() {
+                return;
+            }
+
+            director.number += 1;
+            let next = waves.get(director.number as usize - 1);
+            director.phase = match next {
+                Some(wave) => WavePhase::Spawning {
+                    elapsed: 0.,
+                    spawned: vec![0; wave.groups.len()],
+                    seeds: wave
+                        .groups
+                        .iter()
+                        .map(|g| g.seed.unwrap_or_else(rand::random))
+                        .collect(),
+                },
+                None => WavePhase::Endless,
+            };
+            wswr.send(WaveStarted {
+                number: director.number,
+                endless: next.is_none(),
+            });
+        }
+        WavePhase::Spawning {
+            elapsed,
+            spawned,
+            seeds,
+        } => {
+            let Some(wave) = current else {
+                director.phase = WavePhase::Clearing;
+                return;
+            };
+
+            *elapsed += time.delta_seconds();
+
+            for ((group, spawned), seed) in wave.groups.iter().zip(spawned.iter_mut()).zip(seeds) {
+                let due = group.due(*elapsed);
+                if due <= *spawned {
+                    continue;
+                }
+
+                let Some(eid) = enemy_list.0.get(&group.enemy) else {
+                    warn!(
+                        "Wave {} wants to spawn `{}`, but there's no enemy by that name",
+                        director.number, group.enemy
+                    );
+                    *spawned = group.count;
+                    continue;
+                };
+
+                for index in *spawned..due {
+                    let position = group.pattern.position(
+                        index,
+                        group.count,
+                        spawn_config.spawn_radius,
+                        *seed,
+                    );
+                    let enemy = spawn_enemy(
+                        &mut commands,
+                        eid,
+                        position,
+                        &difficulty_config,
+                        Generation::default(),
+                    );
+                    commands.entity(enemy).insert(WaveEnemy {
+                        wave: director.number,
+                    });
+                }
+                *spawned = due;
+            }
+
+            if wave
+                .groups
+                .iter()
+                .zip(spawned.iter())
+                .all(|(g, s)| *s >= g.count)
+            {
+                director.phase = WavePhase::Clearing;
+            }
+        }
+        WavePhase::Clearing => {
+            if wq.iter().any(|w| w.wave == director.number) {
+                return;
+            }
+
+            wcwr.send(WaveCleared {
+                number: director.number,
+            });
+            let next = waves.get(director.number as usize);
+            director.phase = WavePhase::Resting(rest_before(next));
+        }
+        WavePhase::Endless => {
+            spawn_endless_batch(
+                &mut commands,
+                &mut spawn_config,
+                &difficulty_config,
+                &enemy_pool,
+                &time,
+            );
+        }
+    }
+}
+
+/// Spawns `enemies_per_spawn_batch * modifier` of every type the difficulty allows, every time
+/// the spawn timer goes off
+fn spawn_endless_batch(
+    commands: &mut Commands,
+    config: &mut EnemySpawnConfig,
+    difficulty_config: &DifficultyConfig,
+    enemy_pool: &EnemyPool,
+    time: &Time,
+) {
+    config.timer.tick(time.delta());
+    if !config.timer.finished() {
+        return;
+    }
+
+    let count =
+        difficulty_config.enemies_per_spawn_batch as u32 * difficulty_config.modifier as u32;
+
+    for eid in enemy_pool.0.iter() {
+        // Only spawn enemies if they can spawn at the current difficulty
+        if eid.required_difficulty > difficulty_config.difficulty_level {
+            continue;
+        }
+
+        let seed = rand::random();
+        for index in 0..count {
+            let position = config
+                .pattern
+                .position(index, count, config.spawn_radius, seed);
+            spawn_enemy(
+                commands,
+                eid,
+                position,
+                difficulty_config,
+                Generation::default(),
+            );
+        }
+    }
+}
+
+/// Big text announcing a wave, which fades away on its own
+#[derive(Component)]
+pub struct WaveBanner(pub Timer);
+
+const WAVE_BANNER_DURATION: Duration = Duration::from_millis(2500);
+
+fn show_wave_banners(
+    mut commands: Commands,
+    mut started: EventReader<WaveStarted>,
+    mut cleared: EventReader<WaveCleared>,
+    bq: Query<Entity, With<WaveBanner>>,
+    assets: Res<AppAssets>,
+) {
+    let text = started
+        .read()
+        .map(|e| {
+            if e.endless {
+                "Endless!".to_string()
+            } else {
+                format!("Wave {}", e.number)
+            }
+        })
+        .chain(cleared.read().map(|e| format!("Wave {} cleared", e.number)))
+        .last();
+    let Some(text) = text else {
+        return;
+    };
+
+    // Only ever show the latest news
+    for e in bq.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    top: Val::Percent(30.),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            WaveBanner(Timer::new(WAVE_BANNER_DURATION, TimerMode::Once)),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: assets.font.clone_weak(),
+                    font_size: 80.,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn wave_banners_fade(
+    mut commands: Commands,
+    mut bq: Query<(Entity, &mut WaveBanner, &Children)>,
+    mut tq: Query<&mut Text>,
+    time: Res<Time>,
+) {
+    for (e, mut banner, children) in bq.iter_mut() {
+        banner.0.tick(time.delta());
+        if banner.0.finished() {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+
+        // Hold for a moment, then fade out
+        let alpha = ((1. - banner.0.percent()) * 3.).min(1.);
+        for child in children.iter() {
+            if let Ok(mut text) = tq.get_mut(*child) {
+                for section in text.sections.iter_mut() {
+                    section.style.color.set_a(alpha);
+                }
+            }
+        }
+    }
+}
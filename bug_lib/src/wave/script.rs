@@ -0,0 +1,262 @@
+//! Authored waves, loaded from `.waves.ron` files
+//!
+//! ```ron
+//! (
+//!     waves: [
+//!         (
+//!             rest: 5.0,
+//!             groups: [
+//!                 (enemy: "ant", count: 8, interval: 0.5),
+//!                 (at: 6.0, enemy: "grub", count: 2, pattern: Arc(width: 60.0)),
+//!             ],
+//!         ),
+//!     ],
+//! )
+//! ```
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::enemy::prelude::SpawnPattern;
+
+/// Every scripted wave, played in order before falling back to endless spawning
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveScript {
+    pub waves: Vec<Wave>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wave {
+    /// Seconds of calm before the wave starts
+    #[serde(default)]
+    pub rest: f32,
+    pub groups: Vec<SpawnGroup>,
+}
+
+/// A batch of one type of enemy, trickling in over time
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnGroup {
+    /// Seconds into the wave before the first of the group spawns
+    #[serde(default)]
+    pub at: f32,
+    /// Name of the enemy in the `EnemyList`
+    pub enemy: String,
+    pub count: u32,
+    #[serde(default)]
+    pub pattern: SpawnPattern,
+    /// Seconds between each spawn, zero spawns the whole group at once
+    #[serde(default)]
+    pub interval: f32,
+    /// Fixes where the pattern lands, otherwise it's different every time
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl SpawnGroup {
+    /// How many of the group should have spawned by some time into the wave
+    pub fn due(&self, elapsed: f32) -> u32 {
+        if elapsed < self.at {
+            return 0;
+        }
+        if self.interval <= 0. {
+            return self.count;
+        }
+
+        let due = ((elapsed - self.at) / self.interval) as u32 + 1;
+        due.min(self.count)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WaveScriptError {
+    #[error("could not read wave script: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse wave script: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("wave {wave} has no groups")]
+    EmptyWave { wave: usize },
+    #[error("wave {wave}, group {group}: {field} can't be negative, got {value}")]
+    Negative {
+        wave: usize,
+        group: usize,
+        field: &'static str,
+        value: f32,
+    },
+    #[error("wave {wave}, group {group}: spawns no enemies")]
+    EmptyGroup { wave: usize, group: usize },
+    #[error("wave {wave}, group {group}: there's no enemy called `{enemy}`")]
+    UnknownEnemy {
+        wave: usize,
+        group: usize,
+        enemy: String,
+    },
+}
+
+impl WaveScript {
+    fn validate(&self) -> Result<(), WaveScriptError> {
+        // Numbered from one, like the banner
+        for (wave, w) in self.waves.iter().enumerate().map(|(i, w)| (i + 1, w)) {
+            if w.groups.is_empty() {
+                return Err(WaveScriptError::EmptyWave { wave });
+            }
+
+            for (group, g) in w.groups.iter().enumerate().map(|(i, g)| (i + 1, g)) {
+                for (field, value) in [("rest", w.rest), ("at", g.at), ("interval", g.interval)] {
+                    if !(value >= 0. && value.is_finite()) {
+                        return Err(WaveScriptError::Negative {
+                            wave,
+                            group,
+                            field,
+                            value,
+                        });
+                    }
+                }
+
+                if g.count == 0 || g.enemy.trim().is_empty() {
+                    return Err(WaveScriptError::EmptyGroup { wave, group });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure every group spawns an enemy that exists. Enemy definitions load separately, so
+    /// this can only be checked once they're all in.
+    pub fn check_enemies(&self, exists: impl Fn(&str) -> bool) -> Result<(), WaveScriptError> {
+        for (wave, w) in self.waves.iter().enumerate().map(|(i, w)| (i + 1, w)) {
+            for (group, g) in w.groups.iter().enumerate().map(|(i, g)| (i + 1, g)) {
+                if !exists(&g.enemy) {
+                    return Err(WaveScriptError::UnknownEnemy {
+                        wave,
+                        group,
+                        enemy: g.enemy.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    type Asset = WaveScript;
+    type Settings = ();
+    type Error = WaveScriptError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let script: WaveScript = ron::de::from_bytes(&bytes)?;
+            script.validate()?;
+            Ok(script)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<WaveScript, WaveScriptError> {
+        let script: WaveScript = ron::from_str(source)?;
+        script.validate()?;
+        Ok(script)
+    }
+
+    #[test]
+    fn accepts_a_plain_script() {
+        let script =
+            parse(r#"(waves: [(rest: 2.0, groups: [(enemy: "ant", count: 3, interval: 0.5)])])"#)
+                .unwrap();
+        assert_eq!(script.waves[0].groups[0].count, 3);
+    }
+
+    #[test]
+    fn rejects_empty_waves_and_groups() {
+        assert!(matches!(
+            parse(r#"(waves: [(groups: [])])"#),
+            Err(WaveScriptError::EmptyWave { wave: 1 })
+        ));
+        assert!(matches!(
+            parse(
+                r#"(waves: [
+                    (groups: [(enemy: "ant", count: 1)]),
+                    (groups: [(enemy: "ant", count: 0)]),
+                ])"#
+            ),
+            Err(WaveScriptError::EmptyGroup { wave: 2, group: 1 })
+        ));
+    }
+
+    #[test]
+    fn rejects_negative_times() {
+        for (source, expected) in [
+            (
+                r#"(waves: [(rest: -1.0, groups: [(enemy: "ant", count: 1)])])"#,
+                "rest",
+            ),
+            (
+                r#"(waves: [(groups: [(at: -1.0, enemy: "ant", count: 1)])])"#,
+                "at",
+            ),
+            (
+                r#"(waves: [(groups: [(enemy: "ant", count: 1, interval: -0.5)])])"#,
+                "interval",
+            ),
+        ] {
+            match parse(source) {
+                Err(WaveScriptError::Negative { field, .. }) => assert_eq!(field, expected),
+                other => panic!("{source} should be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_enemies() {
+        let script =
+            parse(r#"(waves: [(groups: [(enemy: "ant", count: 1), (enemy: "wasp", count: 1)])])"#)
+                .unwrap();
+        assert!(script
+            .check_enemies(|name| name == "ant" || name == "wasp")
+            .is_ok());
+        match script.check_enemies(|name| name == "ant") {
+            Err(WaveScriptError::UnknownEnemy { wave, group, enemy }) => {
+                assert_eq!((wave, group, enemy.as_str()), (1, 2, "wasp"));
+            }
+            other => panic!("`wasp` should be unknown, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn groups_trickle_in() {
+        let group: SpawnGroup =
+            ron::from_str(r#"(at: 1.0, enemy: "ant", count: 3, interval: 0.5)"#).unwrap();
+        assert_eq!(group.due(0.5), 0);
+        assert_eq!(group.due(1.0), 1);
+        assert_eq!(group.due(1.6), 2);
+        assert_eq!(group.due(10.0), 3);
+    }
+}
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 
-use crate::collision::Collider;
+use crate::collision::{
+    prelude::{CollisionLayers, Layer},
+    Collider,
+};
 
 pub struct ExperiencePlugin;
 
@@ -11,9 +14,13 @@ impl Plugin for ExperiencePlugin {
 #[derive(Component, Default)]
 pub struct Experience;
 
+/// Experience can only be picked up by the swatter, nothing else should touch it
+pub const EXPERIENCE_LAYERS: CollisionLayers = CollisionLayers::new(Layer::PICKUP, Layer::SWATTER);
+
 #[derive(Bundle, Default)]
 pub struct ExperienceBundle {
     pub collider: Collider,
+    pub layers: CollisionLayers,
     pub sprite_bundle: SpriteBundle,
     pub marker: Experience,
 }